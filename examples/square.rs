@@ -40,8 +40,9 @@ fn game(mut engine: Engine) {
     text.set_string("Hello world!");
     text.pos.x = -200.0;
     text.pos.z = 1.0;
-    text.pos.y = 200.0;
+    text.pos.y = -300.0;
     text.max_width = Some(500.0);
+    text.max_height = Some(500.0);
     text.scale = 50;
     text.color = colors::BLACK;
     strings.push(text);
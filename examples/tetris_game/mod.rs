@@ -1,7 +1,5 @@
-mod audio_engine;
 mod tetris_cluster;
 mod tetris_state;
 
-pub use audio_engine::*;
 pub use tetris_cluster::*;
 pub use tetris_state::*;
@@ -19,9 +19,7 @@ pub struct TetrisState {
     screen: BatchToken,
     clock: Clock,
     is_paused: bool,
-    audio: Bruback,
-    main_sink: SinkID,
-    effect_sink: SinkID,
+    clear_sound: Sound,
 }
 
 impl TetrisState {
@@ -79,14 +77,19 @@ impl TetrisState {
 
         engine.sprite_set(&screen, &sprites);
 
-        let mut bruback = Bruback::new();
-
-        let main_sink = bruback.create_new_sink();
-
-        bruback.set_track_volume(0.05, main_sink);
-        bruback.play_track(String::from("examples/resources/tetris.ogg"), main_sink);
-
-        let effect_sink = bruback.create_new_sink();
+        let music = [Track::new("examples/resources/tetris.ogg")];
+        engine
+            .stream_play(
+                &music,
+                &SoundSettings {
+                    volume: 0.05,
+                    looping: true,
+                    bus: AudioBus::Music,
+                    ..SoundSettings::default()
+                },
+            )
+            .unwrap();
+        let clear_sound = engine.sound_load("examples/resources/clear.wav").unwrap();
 
         TetrisState {
             is_active: true,
@@ -105,9 +108,7 @@ impl TetrisState {
             screen,
             clock,
             is_paused: false,
-            audio: bruback,
-            main_sink,
-            effect_sink,
+            clear_sound,
         }
     }
 
@@ -191,8 +192,11 @@ impl TetrisState {
         }
 
         if cleared_rows != 0 {
-            self.audio.set_track_volume(0.05, self.effect_sink);
-            self.audio.play_track(String::from("examples/resources/clear.wav"), self.effect_sink);
+            let settings = SoundSettings {
+                volume: 0.05,
+                ..SoundSettings::default()
+            };
+            self.engine.sound_play(&self.clear_sound, &settings);
         }
         self.total_lines_cleared += cleared_rows;
 
@@ -0,0 +1,243 @@
+use crate::audio::decoder::Decoder;
+use crate::audio::message::*;
+use crate::audio::stream::{self, StreamWriter};
+use crate::types::*;
+use crate::utility::bounded_spsc;
+use cgmath::*;
+use std::collections::VecDeque;
+use std::io::Cursor;
+use std::time::Duration;
+
+pub struct AudioClient {
+    audio_producer: bounded_spsc::Producer<AudioMessage>,
+    stream_producer: bounded_spsc::Producer<StreamWriter>,
+    // Messages that didn't fit in the queue and can't be dropped, sent before anything newer.
+    overflow: VecDeque<AudioMessage>,
+    voice_count: u32,
+    dropped: u64,
+}
+
+impl AudioClient {
+    pub fn new(
+        audio_producer: bounded_spsc::Producer<AudioMessage>,
+        stream_producer: bounded_spsc::Producer<StreamWriter>,
+    ) -> AudioClient {
+        AudioClient {
+            audio_producer,
+            stream_producer,
+            overflow: VecDeque::new(),
+            voice_count: 0,
+            dropped: 0,
+        }
+    }
+
+    // Sends a message to the audio thread. The game thread never waits on the audio thread, so if
+    // the queue is full a one shot sound is dropped and counted instead. Anything else waits in the
+    // overflow to be sent in order, since losing it would leave a voice playing or unchanged.
+    fn send(&mut self, message: AudioMessage) {
+        self.flush();
+        let message = if self.overflow.is_empty() {
+            match self.audio_producer.try_push(message) {
+                Some(message) => message,
+                None => return,
+            }
+        } else {
+            message
+        };
+        if message.is_one_shot() {
+            if self.dropped == 0 {
+                warn!("The audio queue is full. Audio messages are being dropped.");
+            }
+            self.dropped += 1;
+        } else {
+            self.overflow.push_back(message);
+        }
+    }
+
+    /// Moves as many overflowed messages into the queue as fit.
+    pub fn flush(&mut self) {
+        while let Some(message) = self.overflow.pop_front() {
+            if let Some(message) = self.audio_producer.try_push(message) {
+                self.overflow.push_front(message);
+                return;
+            }
+        }
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    fn next_voice(&mut self) -> VoiceToken {
+        let voice = VoiceToken::new(self.voice_count);
+        self.voice_count = self.voice_count.wrapping_add(1);
+        voice
+    }
+
+    // Starts decoding the playlist, and returns the source to play it from.
+    fn stream_create(&mut self, tracks: &[Track], settings: &SoundSettings) -> Result<Source, &'static str> {
+        if tracks.is_empty() {
+            Err("Playlist has no tracks.")?
+        }
+        for track in tracks {
+            if !track.path.is_file() {
+                Err("Unable to open track.")?
+            }
+        }
+        let (writer, reader) = stream::make(tracks.to_vec(), settings.looping);
+        if self.stream_producer.try_push(writer).is_some() {
+            Err("Too many streams are starting at once.")?
+        }
+        Ok(Source::Stream(reader))
+    }
+
+    pub fn sound_create(&self, bytes: Vec<u8>) -> Result<Sound, &'static str> {
+        let decoder = match Decoder::new(Cursor::new(bytes)) {
+            Some(decoder) => decoder,
+            None => Err("Unable to decode sound.")?,
+        };
+        let channels = decoder.channels();
+        let sample_rate = decoder.sample_rate();
+        if channels == 0 || sample_rate == 0 {
+            Err("Sound has no channels.")?
+        }
+        let samples = decoder.map(|sample| sample as f32 / 32768.0).collect();
+        Ok(Sound::new(samples, channels, sample_rate))
+    }
+
+    pub fn sound_play(&mut self, sound: &Sound, settings: &SoundSettings) -> VoiceToken {
+        self.play(Source::Sound(sound.clone()), settings)
+    }
+
+    pub fn sound_crossfade(
+        &mut self,
+        from: &VoiceToken,
+        sound: &Sound,
+        settings: &SoundSettings,
+        duration: Duration,
+    ) -> VoiceToken {
+        self.crossfade(from, Source::Sound(sound.clone()), settings, duration)
+    }
+
+    pub fn stream_play(
+        &mut self,
+        tracks: &[Track],
+        settings: &SoundSettings,
+    ) -> Result<VoiceToken, &'static str> {
+        let source = self.stream_create(tracks, settings)?;
+        Ok(self.play(source, settings))
+    }
+
+    pub fn stream_crossfade(
+        &mut self,
+        from: &VoiceToken,
+        tracks: &[Track],
+        settings: &SoundSettings,
+        duration: Duration,
+    ) -> Result<VoiceToken, &'static str> {
+        let source = self.stream_create(tracks, settings)?;
+        Ok(self.crossfade(from, source, settings, duration))
+    }
+
+    fn play(&mut self, source: Source, settings: &SoundSettings) -> VoiceToken {
+        let voice = self.next_voice();
+        self.send(AudioMessage::Play {
+            voice,
+            source,
+            settings: *settings,
+        });
+        voice
+    }
+
+    fn crossfade(
+        &mut self,
+        from: &VoiceToken,
+        source: Source,
+        settings: &SoundSettings,
+        duration: Duration,
+    ) -> VoiceToken {
+        let voice = self.next_voice();
+        self.send(AudioMessage::Crossfade {
+            from: *from,
+            voice,
+            source,
+            settings: *settings,
+            duration,
+        });
+        voice
+    }
+
+    pub fn voice_volume(&mut self, voice: &VoiceToken, volume: f32) {
+        self.send(AudioMessage::Volume {
+            voice: *voice,
+            volume,
+        });
+    }
+
+    pub fn voice_pan(&mut self, voice: &VoiceToken, pan: f32) {
+        self.send(AudioMessage::Pan {
+            voice: *voice,
+            pan,
+        });
+    }
+
+    pub fn voice_pitch(&mut self, voice: &VoiceToken, pitch: f32) {
+        self.send(AudioMessage::Pitch {
+            voice: *voice,
+            pitch,
+        });
+    }
+
+    pub fn voice_pause(&mut self, voice: &VoiceToken) {
+        self.send(AudioMessage::Pause {
+            voice: *voice,
+        });
+    }
+
+    pub fn voice_resume(&mut self, voice: &VoiceToken) {
+        self.send(AudioMessage::Resume {
+            voice: *voice,
+        });
+    }
+
+    pub fn voice_stop(&mut self, voice: &VoiceToken) {
+        self.send(AudioMessage::Stop {
+            voice: *voice,
+        });
+    }
+
+    pub fn voice_fade(&mut self, voice: &VoiceToken, volume: f32, duration: Duration, stop: bool) {
+        self.send(AudioMessage::Fade {
+            voice: *voice,
+            volume,
+            duration,
+            stop,
+        });
+    }
+
+    pub fn voice_position(&mut self, voice: &VoiceToken, position: Vector2<f32>) {
+        self.send(AudioMessage::Position {
+            voice: *voice,
+            position,
+        });
+    }
+
+    pub fn listener_position(&mut self, position: Vector2<f32>) {
+        self.send(AudioMessage::Listener {
+            position,
+        });
+    }
+
+    pub fn bus_volume(&mut self, bus: AudioBus, volume: f32) {
+        self.send(AudioMessage::BusVolume {
+            bus,
+            volume,
+        });
+    }
+
+    pub fn bus_ducking(&mut self, ducking: &Ducking) {
+        self.send(AudioMessage::Ducking {
+            ducking: *ducking,
+        });
+    }
+}
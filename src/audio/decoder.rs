@@ -0,0 +1,56 @@
+use std::io::{Read, Seek};
+#[cfg(not(feature = "audio"))]
+use std::marker::PhantomData;
+
+/// Decodes WAV, OGG Vorbis, FLAC, and MP3 into interleaved 16 bit samples. Decoding is only
+/// available with the audio feature, and every source fails to decode without it.
+pub struct Decoder<R: Read + Seek + Send + 'static> {
+    #[cfg(feature = "audio")]
+    inner: rodio::Decoder<R>,
+    #[cfg(not(feature = "audio"))]
+    inner: PhantomData<R>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl<R: Read + Seek + Send + 'static> Decoder<R> {
+    /// Returns None if the source can't be decoded.
+    #[cfg(feature = "audio")]
+    pub fn new(reader: R) -> Option<Decoder<R>> {
+        use rodio::Source;
+        let inner = rodio::Decoder::new(reader).ok()?;
+        Some(Decoder {
+            channels: inner.channels(),
+            sample_rate: inner.sample_rate(),
+            inner,
+        })
+    }
+
+    /// Returns None if the source can't be decoded.
+    #[cfg(not(feature = "audio"))]
+    pub fn new(_reader: R) -> Option<Decoder<R>> {
+        None
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+impl<R: Read + Seek + Send + 'static> Iterator for Decoder<R> {
+    type Item = i16;
+
+    #[cfg(feature = "audio")]
+    fn next(&mut self) -> Option<i16> {
+        self.inner.next()
+    }
+
+    #[cfg(not(feature = "audio"))]
+    fn next(&mut self) -> Option<i16> {
+        None
+    }
+}
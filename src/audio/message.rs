@@ -0,0 +1,84 @@
+use crate::audio::stream::StreamReader;
+use crate::types::*;
+use cgmath::*;
+use std::time::Duration;
+
+/// Audio for a voice to play.
+pub enum Source {
+    Sound(Sound),
+    Stream(StreamReader),
+}
+
+impl Source {
+    /// Returns true if there's nothing to play.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Source::Sound(sound) => sound.frames() == 0,
+            Source::Stream(_) => false,
+        }
+    }
+}
+
+pub enum AudioMessage {
+    Play {
+        voice: VoiceToken,
+        source: Source,
+        settings: SoundSettings,
+    },
+    Volume {
+        voice: VoiceToken,
+        volume: f32,
+    },
+    Pan {
+        voice: VoiceToken,
+        pan: f32,
+    },
+    Pitch {
+        voice: VoiceToken,
+        pitch: f32,
+    },
+    Pause {
+        voice: VoiceToken,
+    },
+    Resume {
+        voice: VoiceToken,
+    },
+    Stop {
+        voice: VoiceToken,
+    },
+    Fade {
+        voice: VoiceToken,
+        volume: f32,
+        duration: Duration,
+        stop: bool,
+    },
+    Crossfade {
+        from: VoiceToken,
+        voice: VoiceToken,
+        source: Source,
+        settings: SoundSettings,
+        duration: Duration,
+    },
+    Position {
+        voice: VoiceToken,
+        position: Vector2<f32>,
+    },
+    Listener {
+        position: Vector2<f32>,
+    },
+    BusVolume {
+        bus: AudioBus,
+        volume: f32,
+    },
+    Ducking {
+        ducking: Ducking,
+    },
+}
+
+impl AudioMessage {
+    /// Returns true if this plays a sound once. Dropping it loses the sound, but doesn't leave any
+    /// other voice playing or unchanged.
+    pub fn is_one_shot(&self) -> bool {
+        matches!(self, AudioMessage::Play { source: Source::Sound(_), settings, .. } if !settings.looping)
+    }
+}
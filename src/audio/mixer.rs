@@ -0,0 +1,754 @@
+use crate::audio::message::*;
+use crate::audio::stream::*;
+use crate::types::*;
+use crate::utility::bounded_spsc;
+use cgmath::*;
+use std::time::Duration;
+
+/// Sample rate the mixer outputs at.
+pub const SAMPLE_RATE: u32 = 44100;
+/// Number of channels the mixer outputs, interleaved left then right.
+pub const CHANNELS: usize = 2;
+// Frames mixed between checks for new messages. Gain changes are ramped over a block so they
+// don't click.
+const BLOCK: usize = 256;
+// Number of buses, indexed by AudioBus::index.
+const BUSES: usize = 3;
+
+// Converts a duration to a number of output frames.
+fn to_frames(duration: Duration) -> usize {
+    (duration.as_secs_f64() * SAMPLE_RATE as f64).round() as usize
+}
+
+// A timed change of a voice's volume.
+struct Fade {
+    volume: f32,
+    // Frames left until the fade ends.
+    remaining: usize,
+    // If the voice stops once the fade ends.
+    stop: bool,
+}
+
+// A stream being played, one chunk at a time.
+struct Stream {
+    reader: StreamReader,
+    // The chunk after the one playing, read early to interpolate across the two.
+    next: Option<Sound>,
+    ended: bool,
+}
+
+impl Stream {
+    fn peek(&mut self) -> Option<&Sound> {
+        if self.next.is_none() && !self.ended {
+            match self.reader.try_pop() {
+                Some(Chunk::Samples(sound)) => self.next = Some(sound),
+                Some(Chunk::End) => self.ended = true,
+                None => {}
+            }
+        }
+        self.next.as_ref()
+    }
+}
+
+struct Voice {
+    token: VoiceToken,
+    // The sound playing, or the chunk playing of a stream.
+    sound: Sound,
+    stream: Option<Stream>,
+    settings: SoundSettings,
+    // Read position in frames of the sound, between frames when resampling.
+    position: f64,
+    paused: bool,
+    stopping: bool,
+    done: bool,
+    fade: Option<Fade>,
+    // Gain of each channel at the end of the last block, including the bus.
+    gain: [f32; 2],
+}
+
+impl Voice {
+    fn new(
+        token: VoiceToken,
+        source: Source,
+        settings: SoundSettings,
+        bus: f32,
+        listener: Vector2<f32>,
+    ) -> Voice {
+        let gains = Self::gains(&settings, listener);
+        let (sound, stream) = match source {
+            Source::Sound(sound) => (sound, None),
+            Source::Stream(reader) => {
+                let stream = Stream {
+                    reader,
+                    next: None,
+                    ended: false,
+                };
+                (Sound::new(Vec::new(), 1, SAMPLE_RATE), Some(stream))
+            }
+        };
+        Voice {
+            token,
+            sound,
+            stream,
+            settings,
+            position: 0.0,
+            paused: false,
+            stopping: false,
+            done: false,
+            fade: None,
+            gain: [gains[0] * bus, gains[1] * bus],
+        }
+    }
+
+    // If the voice can be heard, or will be once it's done fading in.
+    fn active(&self) -> bool {
+        !self.paused && !self.stopping && !self.done
+    }
+
+    // Moves the fade forward by the frames, and stops the voice if it ended a fade out.
+    fn advance(&mut self, frames: usize) {
+        if let Some(fade) = &mut self.fade {
+            if fade.remaining <= frames {
+                self.settings.volume = fade.volume;
+                self.stopping |= fade.stop;
+                self.fade = None;
+            } else {
+                let t = frames as f32 / fade.remaining as f32;
+                self.settings.volume += (fade.volume - self.settings.volume) * t;
+                fade.remaining -= frames;
+            }
+        }
+    }
+
+    // Gain of each channel for the settings, heard from the listener. Panning turns down the
+    // opposite channel, so centered sounds play at their full volume in both.
+    fn gains(settings: &SoundSettings, listener: Vector2<f32>) -> [f32; 2] {
+        let (volume, pan) = match &settings.emitter {
+            Some(emitter) => {
+                let (volume, pan) = emitter.hear(listener);
+                (settings.volume * volume, settings.pan + pan)
+            }
+            None => (settings.volume, settings.pan),
+        };
+        let volume = volume.max(0.0);
+        let pan = pan.clamp(-1.0, 1.0);
+        [volume * (1.0 - pan).min(1.0), volume * (1.0 + pan).min(1.0)]
+    }
+
+    // Reads a frame of the sound as stereo.
+    fn read(sound: &Sound, frame: usize) -> [f32; 2] {
+        let samples = sound.samples();
+        let channels = sound.channels() as usize;
+        [samples[frame * channels], samples[frame * channels + 1.min(channels - 1)]]
+    }
+
+    // Reads the sound at the position, interpolating between frames. Streams interpolate into
+    // their next chunk.
+    fn frame(&mut self, position: f64) -> [f32; 2] {
+        let index = position as usize;
+        let current = Self::read(&self.sound, index);
+        let next = if index + 1 < self.sound.frames() {
+            Self::read(&self.sound, index + 1)
+        } else if let Some(stream) = &mut self.stream {
+            match stream.peek() {
+                Some(next) => Self::read(next, 0),
+                None => current,
+            }
+        } else if self.settings.looping {
+            Self::read(&self.sound, 0)
+        } else {
+            [0.0, 0.0]
+        };
+        let t = (position - index as f64) as f32;
+        [current[0] + (next[0] - current[0]) * t, current[1] + (next[1] - current[1]) * t]
+    }
+
+    // Moves a stream onto the chunk holding the position. Returns false if the chunk hasn't been
+    // decoded yet, or the stream ended.
+    fn next_chunk(&mut self) -> bool {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => return false,
+        };
+        while self.position >= self.sound.frames() as f64 {
+            stream.peek();
+            match stream.next.take() {
+                Some(sound) => {
+                    self.position -= self.sound.frames() as f64;
+                    self.sound = sound;
+                }
+                None => {
+                    self.done = stream.ended;
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // Frames of the sound to move forward for each output frame.
+    fn step(&self) -> f64 {
+        self.sound.sample_rate() as f64 / SAMPLE_RATE as f64 * self.settings.pitch.max(0.0) as f64
+    }
+
+    fn mix(&mut self, output: &mut [f32], bus: f32, listener: Vector2<f32>) {
+        let frames = output.len() / CHANNELS;
+        self.advance(frames);
+        let target = if self.paused || self.stopping {
+            [0.0, 0.0]
+        } else {
+            let gains = Self::gains(&self.settings, listener);
+            [gains[0] * bus, gains[1] * bus]
+        };
+        // Silent voices hold their position until they're resumed.
+        if self.gain == [0.0, 0.0] && target == [0.0, 0.0] {
+            self.done = self.stopping;
+            return;
+        }
+        let mut length = self.sound.frames() as f64;
+        let mut step = self.step();
+        for (index, frame) in output.chunks_mut(CHANNELS).enumerate() {
+            if self.position >= length {
+                if self.stream.is_some() {
+                    // Streams that fall behind hold their position until they catch up.
+                    if !self.next_chunk() {
+                        break;
+                    }
+                    length = self.sound.frames() as f64;
+                    step = self.step();
+                } else if self.settings.looping && length > 0.0 {
+                    self.position %= length;
+                } else {
+                    self.done = true;
+                    break;
+                }
+            }
+            let t = (index + 1) as f32 / frames as f32;
+            let sample = self.frame(self.position);
+            for channel in 0..CHANNELS {
+                let gain = self.gain[channel] + (target[channel] - self.gain[channel]) * t;
+                frame[channel] += sample[channel] * gain;
+            }
+            self.position += step;
+        }
+        self.gain = target;
+        // Stopped voices are removed once they've faded out.
+        if self.stopping {
+            self.done = true;
+        }
+    }
+}
+
+/// Mixes the playing voices into a single stereo stream through the music, effects and dialogue
+/// buses. The mixer is driven by the output device, or by a caller rendering it offline.
+pub struct Mixer {
+    audio_consumer: bounded_spsc::Consumer<AudioMessage>,
+    voices: Vec<Voice>,
+    buses: [f32; BUSES],
+    ducking: Ducking,
+    // Current gain of the music bus from ducking, moving towards the ducked volume while dialogue
+    // plays and back to 1.0 after.
+    duck: f32,
+    listener: Vector2<f32>,
+}
+
+impl Mixer {
+    pub fn new(audio_consumer: bounded_spsc::Consumer<AudioMessage>) -> Mixer {
+        Mixer {
+            audio_consumer,
+            voices: Vec::new(),
+            buses: [1.0; BUSES],
+            ducking: Ducking::default(),
+            duck: 1.0,
+            listener: Vector2::new(0.0, 0.0),
+        }
+    }
+
+    // Gain of the bus, including ducking.
+    fn bus(&self, bus: AudioBus) -> f32 {
+        let gain = self.buses[bus.index()].max(0.0);
+        match bus {
+            AudioBus::Music => gain * self.duck,
+            _ => gain,
+        }
+    }
+
+    // Moves the ducking gain towards its target over the frames.
+    fn duck(&mut self, frames: usize) {
+        let dialogue =
+            self.voices.iter().any(|voice| voice.settings.bus == AudioBus::Dialogue && voice.active());
+        let ducked = self.ducking.volume.clamp(0.0, 1.0);
+        let (target, duration) = if dialogue {
+            (ducked, self.ducking.attack)
+        } else {
+            (1.0, self.ducking.release)
+        };
+        // The step covers the ducked range over the duration. A ducked volume of 1.0 leaves no range
+        // to cover, so the gain snaps back instead, along with gains left over from older settings.
+        let length = to_frames(duration);
+        if length <= frames || ducked >= 1.0 {
+            self.duck = target;
+        } else {
+            let step = (1.0 - ducked) * frames as f32 / length as f32;
+            if self.duck > target {
+                self.duck = (self.duck - step).max(target);
+            } else {
+                self.duck = (self.duck + step).min(target);
+            }
+        }
+    }
+
+    /// Mixes the next frames of audio into the output, interleaved at SAMPLE_RATE. The output is
+    /// overwritten, and should hold a whole number of frames.
+    pub fn render(&mut self, output: &mut [f32]) {
+        for block in output.chunks_mut(BLOCK * CHANNELS) {
+            self.receive();
+            for sample in block.iter_mut() {
+                *sample = 0.0;
+            }
+            self.duck(block.len() / CHANNELS);
+            let buses =
+                [self.bus(AudioBus::Music), self.bus(AudioBus::Effects), self.bus(AudioBus::Dialogue)];
+            for voice in &mut self.voices {
+                voice.mix(block, buses[voice.settings.bus.index()], self.listener);
+            }
+            self.voices.retain(|voice| !voice.done);
+        }
+    }
+
+    fn receive(&mut self) {
+        while let Some(message) = self.audio_consumer.try_pop() {
+            match message {
+                AudioMessage::Play {
+                    voice,
+                    source,
+                    settings,
+                } => {
+                    if !source.is_empty() {
+                        let bus = self.bus(settings.bus);
+                        self.voices.push(Voice::new(voice, source, settings, bus, self.listener));
+                    }
+                }
+                AudioMessage::Volume {
+                    voice,
+                    volume,
+                } => self.with_voice(voice, |voice| voice.settings.volume = volume),
+                AudioMessage::Pan {
+                    voice,
+                    pan,
+                } => self.with_voice(voice, |voice| voice.settings.pan = pan),
+                AudioMessage::Pitch {
+                    voice,
+                    pitch,
+                } => self.with_voice(voice, |voice| voice.settings.pitch = pitch),
+                AudioMessage::Pause {
+                    voice,
+                } => self.with_voice(voice, |voice| voice.paused = true),
+                AudioMessage::Resume {
+                    voice,
+                } => self.with_voice(voice, |voice| voice.paused = false),
+                AudioMessage::Stop {
+                    voice,
+                } => self.with_voice(voice, |voice| voice.stopping = true),
+                AudioMessage::Fade {
+                    voice,
+                    volume,
+                    duration,
+                    stop,
+                } => self.with_voice(voice, |voice| {
+                    voice.fade = Some(Fade {
+                        volume,
+                        remaining: to_frames(duration),
+                        stop,
+                    })
+                }),
+                AudioMessage::Crossfade {
+                    from,
+                    voice,
+                    source,
+                    settings,
+                    duration,
+                } => {
+                    self.with_voice(from, |voice| {
+                        voice.fade = Some(Fade {
+                            volume: 0.0,
+                            remaining: to_frames(duration),
+                            stop: true,
+                        })
+                    });
+                    if !source.is_empty() {
+                        let bus = self.bus(settings.bus);
+                        let mut voice = Voice::new(
+                            voice,
+                            source,
+                            SoundSettings {
+                                volume: 0.0,
+                                ..settings
+                            },
+                            bus,
+                            self.listener,
+                        );
+                        voice.fade = Some(Fade {
+                            volume: settings.volume,
+                            remaining: to_frames(duration),
+                            stop: false,
+                        });
+                        self.voices.push(voice);
+                    }
+                }
+                AudioMessage::Position {
+                    voice,
+                    position,
+                } => self.with_voice(voice, |voice| {
+                    voice.settings.emitter.get_or_insert_with(Emitter::default).position = position
+                }),
+                AudioMessage::Listener {
+                    position,
+                } => self.listener = position,
+                AudioMessage::BusVolume {
+                    bus,
+                    volume,
+                } => self.buses[bus.index()] = volume,
+                AudioMessage::Ducking {
+                    ducking,
+                } => self.ducking = ducking,
+            }
+        }
+    }
+
+    fn with_voice<F: FnOnce(&mut Voice)>(&mut self, token: VoiceToken, f: F) {
+        if let Some(voice) = self.voices.iter_mut().find(|voice| voice.token == token) {
+            f(voice);
+        }
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+    use crate::audio::AudioClient;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    fn mixer() -> (bounded_spsc::Producer<AudioMessage>, Mixer) {
+        let (producer, consumer) = bounded_spsc::make(64);
+        (producer, Mixer::new(consumer))
+    }
+
+    fn play(
+        producer: &bounded_spsc::Producer<AudioMessage>,
+        key: u32,
+        frames: usize,
+        settings: SoundSettings,
+    ) {
+        producer.push(AudioMessage::Play {
+            voice: VoiceToken::new(key),
+            source: Source::Sound(Sound::new(vec![0.5; frames], 1, SAMPLE_RATE)),
+            settings,
+        });
+    }
+
+    #[test]
+    fn playback() {
+        let (producer, mut mixer) = mixer();
+        let mut output = vec![0.0; BLOCK * CHANNELS * 2];
+
+        // A one shot plays once, then is removed.
+        play(&producer, 0, BLOCK + 10, SoundSettings::default());
+        mixer.render(&mut output);
+        assert_eq!(&output[..4], &[0.5, 0.5, 0.5, 0.5]);
+        assert_eq!(output[(BLOCK + 9) * 2], 0.5);
+        assert_eq!(output[(BLOCK + 10) * 2], 0.0);
+        assert!(mixer.voices.is_empty());
+
+        // Panning and pitch.
+        let settings = SoundSettings {
+            pan: -1.0,
+            pitch: 2.0,
+            ..SoundSettings::default()
+        };
+        play(&producer, 1, BLOCK, settings);
+        mixer.render(&mut output);
+        assert_eq!(&output[..2], &[0.5, 0.0]);
+        assert_eq!(output[(BLOCK / 2) * 2], 0.0);
+    }
+
+    #[test]
+    fn voice_control() {
+        let (producer, mut mixer) = mixer();
+        let mut output = vec![0.0; BLOCK * CHANNELS];
+        let settings = SoundSettings {
+            looping: true,
+            ..SoundSettings::default()
+        };
+        play(&producer, 0, 100, settings);
+        mixer.render(&mut output);
+        assert_eq!(output[output.len() - 1], 0.5);
+
+        // Pausing fades out over a block, then holds.
+        producer.push(AudioMessage::Pause {
+            voice: VoiceToken::new(0),
+        });
+        mixer.render(&mut output);
+        assert!(output[2] > 0.0 && output[output.len() - 1] == 0.0);
+        mixer.render(&mut output);
+        assert!(output.iter().all(|&sample| sample == 0.0));
+
+        producer.push(AudioMessage::Resume {
+            voice: VoiceToken::new(0),
+        });
+        producer.push(AudioMessage::Volume {
+            voice: VoiceToken::new(0),
+            volume: 0.5,
+        });
+        mixer.render(&mut output);
+        assert_eq!(output[output.len() - 1], 0.25);
+
+        // Stopping fades out, then removes the voice.
+        producer.push(AudioMessage::Stop {
+            voice: VoiceToken::new(0),
+        });
+        mixer.render(&mut output);
+        assert_eq!(output[output.len() - 1], 0.0);
+        assert!(mixer.voices.is_empty());
+    }
+
+    #[test]
+    fn full_queue_keeps_control() {
+        let (producer, consumer) = bounded_spsc::make(4);
+        let (stream_producer, _stream_consumer) = bounded_spsc::make(1);
+        let mut client = AudioClient::new(producer, stream_producer);
+        let mut mixer = Mixer::new(consumer);
+        let mut output = vec![0.0; BLOCK * CHANNELS];
+        let looping = SoundSettings {
+            looping: true,
+            ..SoundSettings::default()
+        };
+        let sound = Sound::new(vec![0.5; BLOCK * 4], 1, SAMPLE_RATE);
+
+        // Fill the queue behind a looping voice. One shots past the end are dropped, but stopping the
+        // looping voice is held until there's room.
+        let voice = client.sound_play(&sound, &looping);
+        for _ in 0..4 {
+            client.sound_play(&sound, &SoundSettings::default());
+        }
+        client.voice_stop(&voice);
+        assert_eq!(client.dropped(), 1);
+        mixer.render(&mut output);
+        assert_eq!(mixer.voices.len(), 4);
+
+        client.flush();
+        mixer.render(&mut output);
+        assert!(mixer.voices.iter().all(|playing| playing.token != voice));
+        assert_eq!(client.dropped(), 1);
+    }
+
+    #[test]
+    fn buses() {
+        let (producer, mut mixer) = mixer();
+        let mut output = vec![0.0; BLOCK * CHANNELS];
+        producer.push(AudioMessage::BusVolume {
+            bus: AudioBus::Effects,
+            volume: 0.5,
+        });
+        play(&producer, 0, BLOCK * 4, SoundSettings::default());
+        let music = SoundSettings {
+            bus: AudioBus::Music,
+            volume: 0.5,
+            ..SoundSettings::default()
+        };
+        play(&producer, 1, BLOCK * 4, music);
+        mixer.render(&mut output);
+        assert_eq!(output[0], 0.5);
+
+        // Bus changes ramp over a block.
+        producer.push(AudioMessage::BusVolume {
+            bus: AudioBus::Music,
+            volume: 0.0,
+        });
+        mixer.render(&mut output);
+        assert!(output[0] > 0.25 && output[output.len() - 1] == 0.25);
+    }
+
+    #[test]
+    fn ducking() {
+        let (producer, mut mixer) = mixer();
+        let mut output = vec![0.0; BLOCK * CHANNELS];
+        producer.push(AudioMessage::Ducking {
+            ducking: Ducking {
+                volume: 0.5,
+                attack: Duration::from_secs(0),
+                release: Duration::from_secs(1),
+            },
+        });
+        let music = SoundSettings {
+            bus: AudioBus::Music,
+            looping: true,
+            ..SoundSettings::default()
+        };
+        play(&producer, 0, 100, music);
+        mixer.render(&mut output);
+        assert_eq!(output[output.len() - 1], 0.5);
+
+        // Music turns down while dialogue plays.
+        let dialogue = SoundSettings {
+            bus: AudioBus::Dialogue,
+            ..SoundSettings::default()
+        };
+        play(&producer, 1, BLOCK / 2, dialogue);
+        mixer.render(&mut output);
+        assert!(output[0] > 0.5 + 0.25);
+        assert_eq!(output[output.len() - 1], 0.25);
+
+        // Then comes back up over the release.
+        mixer.render(&mut output);
+        let last = output[output.len() - 1];
+        assert!(last > 0.25 && last < 0.5);
+        for _ in 0..SAMPLE_RATE as usize / BLOCK + 1 {
+            mixer.render(&mut output);
+        }
+        assert_eq!(output[output.len() - 1], 0.5);
+
+        // Turning ducking off with a volume of 1.0 brings ducked music straight back up, under the
+        // dialogue that's still playing.
+        play(&producer, 2, BLOCK * 4, dialogue);
+        mixer.render(&mut output);
+        assert_eq!(output[output.len() - 1], 0.25 + 0.5);
+        producer.push(AudioMessage::Ducking {
+            ducking: Ducking {
+                volume: 1.0,
+                attack: Duration::from_secs(1),
+                release: Duration::from_secs(1),
+            },
+        });
+        mixer.render(&mut output);
+        assert_eq!(output[output.len() - 1], 0.5 + 0.5);
+    }
+
+    #[test]
+    fn crossfade() {
+        let (producer, mut mixer) = mixer();
+        let mut output = vec![0.0; BLOCK * CHANNELS];
+        let music = SoundSettings {
+            bus: AudioBus::Music,
+            volume: 0.5,
+            looping: true,
+            ..SoundSettings::default()
+        };
+        play(&producer, 0, 100, music);
+        mixer.render(&mut output);
+        assert_eq!(output[output.len() - 1], 0.25);
+
+        producer.push(AudioMessage::Crossfade {
+            from: VoiceToken::new(0),
+            voice: VoiceToken::new(1),
+            source: Source::Sound(Sound::new(vec![1.0; 100], 1, SAMPLE_RATE)),
+            settings: music,
+            duration: Duration::from_secs_f64((BLOCK * 4) as f64 / SAMPLE_RATE as f64),
+        });
+        mixer.render(&mut output);
+        mixer.render(&mut output);
+        // Halfway through, both tracks play at half their volume.
+        assert!((output[output.len() - 1] - (0.125 + 0.25)).abs() < 1e-5);
+        mixer.render(&mut output);
+        mixer.render(&mut output);
+        assert!((output[output.len() - 1] - 0.5).abs() < 1e-5);
+        assert_eq!(mixer.voices.len(), 1);
+    }
+
+    #[test]
+    fn positional() {
+        let (producer, mut mixer) = mixer();
+        let mut output = vec![0.0; BLOCK * CHANNELS];
+        let settings = SoundSettings {
+            looping: true,
+            emitter: Some(Emitter {
+                position: Vector2::new(0.0, 0.0),
+                rolloff: Rolloff::Linear,
+                near: 10.0,
+                far: 110.0,
+            }),
+            ..SoundSettings::default()
+        };
+        play(&producer, 0, 100, settings);
+        mixer.render(&mut output);
+        assert_eq!(&output[output.len() - 2..], &[0.5, 0.5]);
+
+        // Halfway to the far distance on the right, at half volume and only in the right speaker.
+        producer.push(AudioMessage::Listener {
+            position: Vector2::new(-60.0, 0.0),
+        });
+        mixer.render(&mut output);
+        assert_eq!(&output[output.len() - 2..], &[0.0, 0.25]);
+
+        // Within the near distance, the sound is only panned a little.
+        producer.push(AudioMessage::Position {
+            voice: VoiceToken::new(0),
+            position: Vector2::new(-55.0, 0.0),
+        });
+        mixer.render(&mut output);
+        assert_eq!(&output[output.len() - 2..], &[0.25, 0.5]);
+
+        // Past the far distance, linear rolloff is silent.
+        producer.push(AudioMessage::Listener {
+            position: Vector2::new(0.0, 200.0),
+        });
+        mixer.render(&mut output);
+        assert_eq!(&output[output.len() - 2..], &[0.0, 0.0]);
+    }
+
+    #[test]
+    fn rolloff() {
+        let listener = Vector2::new(0.0, 0.0);
+        let emitter = |rolloff, x| Emitter {
+            position: Vector2::new(x, 0.0),
+            rolloff,
+            near: 10.0,
+            far: 100.0,
+        };
+        assert_eq!(emitter(Rolloff::Inverse, 5.0).hear(listener).0, 1.0);
+        assert_eq!(emitter(Rolloff::Inverse, 20.0).hear(listener).0, 0.5);
+        assert_eq!(emitter(Rolloff::Inverse, 500.0).hear(listener).0, 0.1);
+        assert_eq!(emitter(Rolloff::Exponential(2.0), 20.0).hear(listener).0, 0.25);
+        assert_eq!(emitter(Rolloff::Linear, 55.0).hear(listener).0, 0.5);
+    }
+
+    #[test]
+    fn streaming() {
+        let (producer, mut mixer) = mixer();
+        let mut output = vec![0.0; BLOCK * CHANNELS];
+        let (chunks, consumer) = bounded_spsc::make(8);
+        let closed = Arc::new(AtomicBool::new(false));
+        producer.push(AudioMessage::Play {
+            voice: VoiceToken::new(0),
+            source: Source::Stream(StreamReader::new(consumer, closed.clone())),
+            settings: SoundSettings::default(),
+        });
+
+        // Nothing's been decoded yet, so the stream waits.
+        mixer.render(&mut output);
+        assert!(output.iter().all(|&sample| sample == 0.0));
+        assert_eq!(mixer.voices.len(), 1);
+
+        // Chunks play back to back.
+        chunks.push(Chunk::Samples(Sound::new(vec![0.25; BLOCK / 2], 1, SAMPLE_RATE)));
+        chunks.push(Chunk::Samples(Sound::new(vec![0.5; BLOCK], 1, SAMPLE_RATE)));
+        chunks.push(Chunk::End);
+        let mut output = vec![0.0; BLOCK * CHANNELS * 2];
+        mixer.render(&mut output);
+        assert_eq!(output[0], 0.25);
+        assert_eq!(output[(BLOCK / 2 - 1) * 2], 0.25);
+        assert_eq!(output[(BLOCK / 2) * 2], 0.5);
+        assert_eq!(output[(BLOCK + BLOCK / 2 - 1) * 2], 0.5);
+        assert_eq!(output[(BLOCK + BLOCK / 2) * 2], 0.0);
+
+        // Ended streams are removed, closing the reader.
+        assert!(mixer.voices.is_empty());
+        assert!(closed.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,10 @@
+mod client;
+mod decoder;
+mod message;
+mod mixer;
+mod server;
+mod stream;
+
+pub(crate) use crate::audio::client::*;
+pub(crate) use crate::audio::server::*;
+pub(crate) use crate::audio::stream::StreamDecoder;
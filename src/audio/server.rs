@@ -0,0 +1,92 @@
+use crate::audio::message::*;
+use crate::audio::mixer::*;
+use crate::utility::bounded_spsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Frames mixed at a time for the output.
+const OUTPUT_FRAMES: usize = 1024;
+
+/// Plays the mixer on the default output device. Without the audio feature, the mixer always runs
+/// against the null output, which discards what's mixed.
+pub struct AudioServer {
+    mixer: Mixer,
+    buffer: Vec<f32>,
+    cursor: usize,
+}
+
+impl AudioServer {
+    /// Starts playing audio. Without an output device, like on headless machines, the audio is
+    /// mixed in real time and discarded instead, so voices still play through and finish.
+    pub fn start(audio_consumer: bounded_spsc::Consumer<AudioMessage>) {
+        let server = AudioServer {
+            mixer: Mixer::new(audio_consumer),
+            buffer: vec![0.0; OUTPUT_FRAMES * CHANNELS],
+            cursor: OUTPUT_FRAMES * CHANNELS,
+        };
+        #[cfg(feature = "audio")]
+        match rodio::default_output_device() {
+            Some(device) => {
+                info!("Audio started.");
+                rodio::play_raw(&device, server);
+            }
+            None => {
+                warn!("No audio output device found. Audio will be discarded.");
+                thread::spawn(move || server.discard());
+            }
+        }
+        #[cfg(not(feature = "audio"))]
+        {
+            info!("Built without the audio feature. Audio will be discarded.");
+            thread::spawn(move || server.discard());
+        }
+    }
+
+    // Mixes audio at the rate a device would play it, without playing it.
+    fn discard(mut self) {
+        let period = Duration::from_secs_f64(OUTPUT_FRAMES as f64 / SAMPLE_RATE as f64);
+        let mut next = Instant::now();
+        loop {
+            self.mixer.render(&mut self.buffer);
+            next += period;
+            let now = Instant::now();
+            if next > now {
+                thread::sleep(next - now);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl Iterator for AudioServer {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.cursor == self.buffer.len() {
+            self.mixer.render(&mut self.buffer);
+            self.cursor = 0;
+        }
+        let sample = self.buffer[self.cursor];
+        self.cursor += 1;
+        Some(sample)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl rodio::Source for AudioServer {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        CHANNELS as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
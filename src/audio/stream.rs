@@ -0,0 +1,395 @@
+use crate::audio::decoder::Decoder;
+use crate::types::*;
+use crate::utility::bounded_spsc;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// Frames decoded into each chunk.
+const CHUNK_FRAMES: usize = 4096;
+// Chunks buffered ahead of playback for each stream, about 1.5 seconds of audio at 44.1kHz.
+const CHUNKS: usize = 16;
+// How long the decoder sleeps for once every stream's buffer is full.
+const IDLE: Duration = Duration::from_millis(10);
+
+/// Decoded audio, in the order it plays.
+pub enum Chunk {
+    Samples(Sound),
+    End,
+}
+
+/// The playing end of a stream, read by the mixer. Dropping the reader ends the stream.
+pub struct StreamReader {
+    chunks: bounded_spsc::Consumer<Chunk>,
+    closed: Arc<AtomicBool>,
+}
+
+impl StreamReader {
+    pub fn new(chunks: bounded_spsc::Consumer<Chunk>, closed: Arc<AtomicBool>) -> StreamReader {
+        StreamReader {
+            chunks,
+            closed,
+        }
+    }
+
+    /// Returns the next chunk if it's been decoded.
+    pub fn try_pop(&self) -> Option<Chunk> {
+        self.chunks.try_pop()
+    }
+}
+
+impl Drop for StreamReader {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Creates a stream over the playlist, returning its decoding and playing ends.
+pub fn make(tracks: Vec<Track>, looping: bool) -> (StreamWriter, StreamReader) {
+    let (producer, consumer) = bounded_spsc::make(CHUNKS);
+    let closed = Arc::new(AtomicBool::new(false));
+    let writer = StreamWriter {
+        chunks: producer,
+        closed: closed.clone(),
+        tracks,
+        looping,
+        index: 0,
+        decoder: None,
+        pending: None,
+        silent: 0,
+        done: false,
+    };
+    (writer, StreamReader::new(consumer, closed))
+}
+
+// Decodes a single track, repeating its loop points.
+struct TrackDecoder {
+    track: Track,
+    decoder: Decoder<BufReader<File>>,
+    channels: u16,
+    sample_rate: u32,
+    // Frames read from the start of the track.
+    frame: usize,
+    // Frame the loop last started from, to stop looping sections with nothing in them.
+    looped: Option<usize>,
+    // A second decoder of the track, moved ahead to the loop's start while the buffer is full, so
+    // looping doesn't wait on decoding the track up to it.
+    rewind: Option<Box<TrackDecoder>>,
+}
+
+impl TrackDecoder {
+    fn open(track: &Track) -> Result<TrackDecoder, &'static str> {
+        let file = match File::open(&track.path) {
+            Ok(file) => file,
+            Err(_) => Err("Unable to open track.")?,
+        };
+        let decoder = match Decoder::new(BufReader::new(file)) {
+            Some(decoder) => decoder,
+            None => Err("Unable to decode track.")?,
+        };
+        let channels = decoder.channels();
+        let sample_rate = decoder.sample_rate();
+        if channels == 0 || sample_rate == 0 {
+            Err("Track has no channels.")?
+        }
+        Ok(TrackDecoder {
+            track: track.clone(),
+            decoder,
+            channels,
+            sample_rate,
+            frame: 0,
+            looped: None,
+            rewind: None,
+        })
+    }
+
+    fn to_frame(&self, time: Duration) -> usize {
+        (time.as_secs_f64() * self.sample_rate as f64).round() as usize
+    }
+
+    // Discards up to the frames, stopping at the frame. Returns the number of frames discarded.
+    fn skip(&mut self, frames: usize, to: usize) -> usize {
+        let channels = self.channels as usize;
+        let mut skipped = 0;
+        while skipped < frames && self.frame < to {
+            for _ in 0..channels {
+                if self.decoder.next().is_none() {
+                    return skipped;
+                }
+            }
+            self.frame += 1;
+            skipped += 1;
+        }
+        skipped
+    }
+
+    // Moves the rewind decoder towards the loop's start. Returns true if there was work to do.
+    fn prepare(&mut self) -> bool {
+        let start = match self.track.loop_points {
+            Some(points) => self.to_frame(points.start),
+            None => return false,
+        };
+        match &mut self.rewind {
+            Some(rewind) => rewind.skip(CHUNK_FRAMES, start) > 0,
+            None => match TrackDecoder::open(&self.track) {
+                Ok(rewind) => {
+                    self.rewind = Some(Box::new(rewind));
+                    true
+                }
+                Err(_) => false,
+            },
+        }
+    }
+
+    // Decodes up to the frames, stopping early at the end of the loop. Returns None once the track
+    // has nothing left to play.
+    fn read(&mut self, frames: usize) -> Option<Sound> {
+        let channels = self.channels as usize;
+        loop {
+            let end = self.track.loop_points.and_then(|points| points.end).map(|end| self.to_frame(end));
+            let limit = match end {
+                Some(end) => end.saturating_sub(self.frame).min(frames),
+                None => frames,
+            };
+            let mut samples = Vec::with_capacity(limit * channels);
+            while samples.len() < limit * channels {
+                match self.decoder.next() {
+                    Some(sample) => samples.push(sample as f32 / 32768.0),
+                    None => break,
+                }
+            }
+            samples.truncate(samples.len() / channels * channels);
+            if !samples.is_empty() {
+                self.frame += samples.len() / channels;
+                return Some(Sound::new(samples, self.channels, self.sample_rate));
+            }
+            // Reached the end of the loop or the track.
+            if !self.restart() {
+                return None;
+            }
+        }
+    }
+
+    // Jumps back to the start of the loop. Returns false if the track doesn't loop, or the loop
+    // played nothing since it last started.
+    fn restart(&mut self) -> bool {
+        let start = match self.track.loop_points {
+            Some(points) => self.to_frame(points.start),
+            None => return false,
+        };
+        if self.looped == Some(self.frame) {
+            return false;
+        }
+        // Decoders can't seek, so the track is decoded again up to the loop's start. This is
+        // usually done ahead of time by prepare.
+        let mut rewind = match self.rewind.take() {
+            Some(rewind) => *rewind,
+            None => match TrackDecoder::open(&self.track) {
+                Ok(rewind) => rewind,
+                Err(message) => {
+                    warn!("{}", message);
+                    return false;
+                }
+            },
+        };
+        while rewind.skip(CHUNK_FRAMES, start) > 0 {}
+        rewind.looped = Some(rewind.frame);
+        *self = rewind;
+        true
+    }
+}
+
+/// The decoding end of a stream, filled by the stream decoder.
+pub struct StreamWriter {
+    chunks: bounded_spsc::Producer<Chunk>,
+    closed: Arc<AtomicBool>,
+    tracks: Vec<Track>,
+    looping: bool,
+    index: usize,
+    decoder: Option<TrackDecoder>,
+    // A chunk that didn't fit in the buffer yet.
+    pending: Option<Chunk>,
+    // Tracks opened in a row without playing anything, to stop looping playlists with nothing in
+    // them.
+    silent: usize,
+    done: bool,
+}
+
+impl StreamWriter {
+    // Decodes into the buffer until it's full. Returns true if anything was decoded.
+    fn fill(&mut self) -> bool {
+        let mut progress = false;
+        while !self.done {
+            if self.closed.load(Ordering::Relaxed) {
+                self.done = true;
+                break;
+            }
+            let chunk = match self.pending.take() {
+                Some(chunk) => chunk,
+                None => self.decode(),
+            };
+            let end = matches!(chunk, Chunk::End);
+            if let Some(chunk) = self.chunks.try_push(chunk) {
+                self.pending = Some(chunk);
+                if let Some(decoder) = &mut self.decoder {
+                    progress |= decoder.prepare();
+                }
+                break;
+            }
+            progress = true;
+            self.done = end;
+        }
+        progress
+    }
+
+    // Decodes the next chunk, moving through the playlist without a gap between tracks.
+    fn decode(&mut self) -> Chunk {
+        loop {
+            if self.decoder.is_none() {
+                if self.index == self.tracks.len() && self.looping {
+                    self.index = 0;
+                }
+                if self.index == self.tracks.len() || self.silent > self.tracks.len() {
+                    return Chunk::End;
+                }
+                match TrackDecoder::open(&self.tracks[self.index]) {
+                    Ok(decoder) => self.decoder = Some(decoder),
+                    Err(message) => warn!("{} {:?}", message, self.tracks[self.index].path),
+                }
+                self.silent += 1;
+            }
+            if let Some(decoder) = &mut self.decoder {
+                if let Some(sound) = decoder.read(CHUNK_FRAMES) {
+                    self.silent = 0;
+                    return Chunk::Samples(sound);
+                }
+            }
+            self.decoder = None;
+            self.index += 1;
+        }
+    }
+}
+
+/// Decodes every playing stream on a dedicated thread, keeping their buffers full.
+pub struct StreamDecoder {
+    stream_consumer: bounded_spsc::Consumer<StreamWriter>,
+    streams: Vec<StreamWriter>,
+}
+
+impl StreamDecoder {
+    pub fn start(stream_consumer: bounded_spsc::Consumer<StreamWriter>) {
+        let decoder = StreamDecoder {
+            stream_consumer,
+            streams: Vec::new(),
+        };
+        thread::spawn(move || decoder.run());
+    }
+
+    fn run(mut self) {
+        loop {
+            while let Some(stream) = self.stream_consumer.try_pop() {
+                self.streams.push(stream);
+            }
+            let mut progress = false;
+            for stream in &mut self.streams {
+                progress |= stream.fill();
+            }
+            self.streams.retain(|stream| !stream.done);
+            if !progress {
+                thread::sleep(IDLE);
+            }
+        }
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(all(test, feature = "audio"))]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // Writes a mono 16 bit WAV at 1kHz, where each frame holds its index.
+    fn wav(name: &str, frames: usize) -> PathBuf {
+        let mut bytes = Vec::new();
+        let data = (frames * 2) as u32;
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1000u32.to_le_bytes());
+        bytes.extend_from_slice(&2000u32.to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data.to_le_bytes());
+        for frame in 0..frames {
+            bytes.extend_from_slice(&(frame as i16).to_le_bytes());
+        }
+        let path = std::env::temp_dir().join(format!("storm_stream_{}_{}.wav", std::process::id(), name));
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    // Reads the frames the stream plays, as the indices written by wav.
+    fn frames(writer: &mut StreamWriter, reader: &StreamReader, limit: usize) -> Vec<i32> {
+        let mut frames = Vec::new();
+        while frames.len() < limit {
+            writer.fill();
+            match reader.try_pop() {
+                Some(Chunk::Samples(sound)) => {
+                    frames.extend(sound.samples().iter().map(|&sample| (sample * 32768.0).round() as i32))
+                }
+                Some(Chunk::End) | None => break,
+            }
+        }
+        frames
+    }
+
+    #[test]
+    fn playlist_and_loop_points() {
+        let intro = wav("intro", 5000);
+        let song = wav("song", 10);
+
+        // Tracks follow each other without a gap.
+        let (mut writer, reader) = make(vec![Track::new(&intro), Track::new(&song)], false);
+        let played = frames(&mut writer, &reader, usize::max_value());
+        assert_eq!(played.len(), 5010);
+        assert_eq!(&played[4998..5002], &[4998, 4999, 0, 1]);
+        assert!(writer.done);
+
+        // Loop points repeat a section of the track.
+        let track = Track {
+            path: song.clone(),
+            loop_points: Some(LoopPoints {
+                start: Duration::from_millis(2),
+                end: Some(Duration::from_millis(5)),
+            }),
+        };
+        let (mut writer, reader) = make(vec![track], false);
+        let played = frames(&mut writer, &reader, 11);
+        assert_eq!(&played[..11], &[0, 1, 2, 3, 4, 2, 3, 4, 2, 3, 4]);
+
+        // Dropping the reader ends the stream.
+        drop(reader);
+        writer.fill();
+        assert!(writer.done);
+
+        // Looping playlists start over.
+        let (mut writer, reader) = make(vec![Track::new(&song)], true);
+        let played = frames(&mut writer, &reader, 12);
+        assert_eq!(&played[8..12], &[8, 9, 0, 1]);
+
+        fs::remove_file(intro).unwrap();
+        fs::remove_file(song).unwrap();
+    }
+}
@@ -6,11 +6,14 @@ pub extern crate log;
 pub use cgmath;
 
 pub mod math;
+pub mod profile;
 pub mod time;
+pub mod tween;
 
 pub use crate::input::*;
 pub use crate::types::*;
 
+mod audio;
 mod input;
 mod render;
 mod text;
@@ -18,19 +21,29 @@ mod texture;
 mod types;
 mod utility;
 
-use crate::render::{RenderClient, RenderServer};
+use crate::audio::{AudioClient, AudioServer, StreamDecoder};
+use crate::profile::{Overlay, ProfileReport, ProfileThread, Profiler};
+use crate::render::{RenderClient, RenderFrame, RenderServer};
+use crate::text::BitmapFont;
 use crate::utility::{bounded_spsc, control, swap_spsc};
-use std::fs::File;
+use cgmath::*;
+use std::fs::{self, File};
 use std::io::BufReader;
 use std::io::Read;
 use std::path::Path;
 use std::thread;
+use std::time::Duration;
 
 /// The main entry point into the Storm engine. All interactions with the engine are managed by the
 /// API on this type. The engine is send, and can be moved between threads.
 pub struct Engine {
     render_client: RenderClient,
     input_client: InputClient,
+    audio_client: AudioClient,
+    frame_consumer: bounded_spsc::Consumer<RenderFrame>,
+    profiler: Profiler,
+    render_stats: RenderStats,
+    overlay: Option<Overlay>,
 }
 
 impl Engine {
@@ -45,16 +58,27 @@ impl Engine {
         // Inter-thread messaging.
         let (render_producer_pipe, render_consumer_pipe) = swap_spsc::make();
         let (input_producer_pipe, input_consumer_pipe) = bounded_spsc::make(512);
+        let (audio_producer_pipe, audio_consumer_pipe) = bounded_spsc::make(1024);
+        let (stream_producer_pipe, stream_consumer_pipe) = bounded_spsc::make(64);
+        let (frame_producer_pipe, frame_consumer_pipe) = bounded_spsc::make(256);
         let (engine_watcher, engine_probe) = control::make_probe();
 
         // Rendering and input
-        let mut render_server = RenderServer::new(&desc, &event_loop, render_consumer_pipe);
+        let mut render_server =
+            RenderServer::new(&desc, &event_loop, render_consumer_pipe, frame_producer_pipe);
         let mut input_server = InputServer::new(input_producer_pipe);
+        AudioServer::start(audio_consumer_pipe);
+        StreamDecoder::start(stream_consumer_pipe);
 
         thread::spawn(move || {
             let engine = Engine {
                 render_client: RenderClient::new(render_producer_pipe),
                 input_client: InputClient::new(input_consumer_pipe),
+                audio_client: AudioClient::new(audio_producer_pipe, stream_producer_pipe),
+                frame_consumer: frame_consumer_pipe,
+                profiler: Profiler::new(),
+                render_stats: RenderStats::default(),
+                overlay: None,
             };
             info!("Game started.");
             game_loop(engine);
@@ -100,7 +124,138 @@ impl Engine {
     // Audio
     // ////////////////////////////////////////////////////////
 
-    // TODO: Audio
+    /// Loads and decodes a sound from the given path. WAV, OGG Vorbis, FLAC, and MP3 are
+    /// supported. The whole sound is decoded into memory, so it can be played back any number of
+    /// times without decoding it again.
+    ///
+    /// Returns an error if the file can't be read or decoded. Decoding needs the audio feature,
+    /// which is on by default.
+    pub fn sound_load<P: AsRef<Path>>(&mut self, path: P) -> Result<Sound, &str> {
+        let bytes = read_file(path)?;
+        self.audio_client.sound_create(bytes)
+    }
+
+    /// Decodes a sound from an in memory source, like include_bytes!(). The supported formats are
+    /// the same as sound_load.
+    ///
+    /// Returns an error if the bytes can't be decoded.
+    pub fn sound_load_bytes(&mut self, bytes: &[u8]) -> Result<Sound, &'static str> {
+        self.audio_client.sound_create(bytes.to_vec())
+    }
+
+    /// Starts playing a sound, and returns a token to control it with while it plays. A sound can
+    /// be played any number of times at once.
+    pub fn sound_play(&mut self, sound: &Sound, settings: &SoundSettings) -> VoiceToken {
+        self.audio_client.sound_play(sound, settings)
+    }
+
+    /// Starts playing a sound while fading out another over the duration, like moving from one
+    /// music track to the next. The new sound fades in from silence to the volume in its settings,
+    /// and the old one is stopped once it's silent.
+    pub fn sound_crossfade(
+        &mut self,
+        from: &VoiceToken,
+        sound: &Sound,
+        settings: &SoundSettings,
+        duration: Duration,
+    ) -> VoiceToken {
+        self.audio_client.sound_crossfade(from, sound, settings, duration)
+    }
+
+    /// Starts streaming a playlist of tracks, and returns a token to control it with while it
+    /// plays. Tracks are decoded a little at a time on a dedicated thread as they play, instead of
+    /// all at once, which suits long sounds like music. Each track follows the last without a gap,
+    /// and looping settings start the playlist over once it ends.
+    ///
+    /// Returns an error if the playlist is empty or a track's file doesn't exist. Tracks that
+    /// can't be decoded are skipped with a warning.
+    pub fn stream_play(&mut self, tracks: &[Track], settings: &SoundSettings) -> Result<VoiceToken, &str> {
+        self.audio_client.stream_play(tracks, settings)
+    }
+
+    /// Starts streaming a playlist of tracks while fading out another sound over the duration. This
+    /// works like sound_crossfade, and returns the same errors as stream_play.
+    pub fn stream_crossfade(
+        &mut self,
+        from: &VoiceToken,
+        tracks: &[Track],
+        settings: &SoundSettings,
+        duration: Duration,
+    ) -> Result<VoiceToken, &str> {
+        self.audio_client.stream_crossfade(from, tracks, settings, duration)
+    }
+
+    /// Sets the volume of a playing sound, where 1.0 is its original volume.
+    pub fn voice_volume(&mut self, voice: &VoiceToken, volume: f32) {
+        self.audio_client.voice_volume(voice, volume);
+    }
+
+    /// Sets the position of a playing sound between the speakers, from -1.0 for only the left to
+    /// 1.0 for only the right.
+    pub fn voice_pan(&mut self, voice: &VoiceToken, pan: f32) {
+        self.audio_client.voice_pan(voice, pan);
+    }
+
+    /// Sets the speed of a playing sound, which also shifts its pitch.
+    pub fn voice_pitch(&mut self, voice: &VoiceToken, pitch: f32) {
+        self.audio_client.voice_pitch(voice, pitch);
+    }
+
+    /// Pauses a playing sound. It picks up where it left off when resumed.
+    pub fn voice_pause(&mut self, voice: &VoiceToken) {
+        self.audio_client.voice_pause(voice);
+    }
+
+    /// Resumes a paused sound.
+    pub fn voice_resume(&mut self, voice: &VoiceToken) {
+        self.audio_client.voice_resume(voice);
+    }
+
+    /// Stops a playing sound. Stopped sounds can't be resumed.
+    pub fn voice_stop(&mut self, voice: &VoiceToken) {
+        self.audio_client.voice_stop(voice);
+    }
+
+    /// Moves the volume of a playing sound to the given volume over the duration.
+    pub fn voice_fade(&mut self, voice: &VoiceToken, volume: f32, duration: Duration) {
+        self.audio_client.voice_fade(voice, volume, duration, false);
+    }
+
+    /// Fades a playing sound out over the duration, then stops it.
+    pub fn voice_fade_out(&mut self, voice: &VoiceToken, duration: Duration) {
+        self.audio_client.voice_fade(voice, 0.0, duration, true);
+    }
+
+    /// Moves where a playing sound plays from, in the same units as Sprite::pos. Sounds played
+    /// without an emitter are given the default one at the position.
+    pub fn voice_position(&mut self, voice: &VoiceToken, position: Vector2<f32>) {
+        self.audio_client.voice_position(voice, position);
+    }
+
+    /// Moves the listener that positioned sounds are heard from, in the same units as Sprite::pos.
+    /// This is usually the point in the batch the camera is centered on, so sounds pan with what's
+    /// on screen. The listener starts at the origin.
+    pub fn listener_position(&mut self, position: Vector2<f32>) {
+        self.audio_client.listener_position(position);
+    }
+
+    /// Sets the volume of every sound played on the bus, where 1.0 is their original volume. All
+    /// buses start at 1.0.
+    pub fn bus_volume(&mut self, bus: AudioBus, volume: f32) {
+        self.audio_client.bus_volume(bus, volume);
+    }
+
+    /// Sets how the music bus is turned down while sounds play on the dialogue bus.
+    pub fn bus_ducking(&mut self, ducking: &Ducking) {
+        self.audio_client.bus_ducking(ducking);
+    }
+
+    /// Returns the number of sounds dropped so far because the audio thread fell behind. Calls never
+    /// wait on the audio thread, so when its queue is full, sounds played once without looping are
+    /// dropped. Other calls, like stopping a voice, are held and sent once there's room.
+    pub fn audio_dropped(&self) -> u64 {
+        self.audio_client.dropped()
+    }
 
     // ////////////////////////////////////////////////////////
     // Batch
@@ -144,12 +299,84 @@ impl Engine {
     // Text
     // ////////////////////////////////////////////////////////
 
-    /// Loads a new font and returns a token to reference it with later.
-    pub fn font_load(&mut self, path: &str) -> FontToken {
-        self.render_client.font_create(path)
+    /// Loads a new font from a given path and returns a token to reference it with later.
+    ///
+    /// Returns an error if the file can't be read or isn't a valid font.
+    pub fn font_load<P: AsRef<Path>>(&mut self, path: P) -> Result<FontToken, &str> {
+        let bytes = read_file(path)?;
+        self.render_client.font_create(&bytes, false)
+    }
+
+    /// Loads a new font drawn with signed distance fields, and returns a token to reference it with
+    /// later. Each glyph is rasterized once and scaled to every size, so text stays sharp when
+    /// scaled or zoomed without filling the atlas with a copy of each glyph per size. Small text is
+    /// slightly softer than with font_load.
+    ///
+    /// Returns an error if the file can't be read or isn't a valid font.
+    pub fn font_load_sdf<P: AsRef<Path>>(&mut self, path: P) -> Result<FontToken, &str> {
+        let bytes = read_file(path)?;
+        self.render_client.font_create(&bytes, true)
+    }
+
+    /// Loads a bitmap font from an AngelCode BMFont descriptor (.fnt) in either the text or binary
+    /// format, and returns a token to reference it with later. The page images named by the
+    /// descriptor are loaded from paths relative to it, and added to the texture atlas.
+    ///
+    /// Bitmap fonts are drawn at whole multiples of the size they were made at, picked to be
+    /// closest to the text's scale, so their pixels stay crisp. Kerning pairs from the descriptor
+    /// are applied. Text drawn with a bitmap font draws all of its spans with bitmap fonts, using
+    /// the text's font for spans set to any other font, and supports shadows but not outlines or
+    /// glows.
+    ///
+    /// Returns an error if a file can't be read, or the descriptor or a page isn't valid.
+    pub fn font_load_bitmap<P: AsRef<Path>>(&mut self, path: P) -> Result<FontToken, &str> {
+        let path = path.as_ref();
+        let font = BitmapFont::parse(&read_file(path)?)?;
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut pages = Vec::with_capacity(font.pages().len());
+        for page in font.pages() {
+            pages.push(read_file(directory.join(page))?);
+        }
+        self.render_client.font_create_bitmap(font, &pages)
+    }
+
+    /// Loads a new font from an in memory source, like include_bytes!(), and returns a token to
+    /// reference it with later. If sdf is true, the font is drawn with signed distance fields as
+    /// described in font_load_sdf.
+    ///
+    /// Returns an error if the bytes aren't a valid font.
+    pub fn font_load_bytes(&mut self, bytes: &[u8], sdf: bool) -> Result<FontToken, &'static str> {
+        self.render_client.font_create(bytes, sdf)
     }
 
-    // TODO: Alternative font loading functions.
+    /// Replaces the engine default font, used by FontToken::default(), with the given font. Text
+    /// already set on batches keeps its glyphs until it's set again. If sdf is true, the font is
+    /// drawn with signed distance fields as described in font_load_sdf.
+    ///
+    /// Returns an error if the bytes aren't a valid font, in which case the default is unchanged.
+    pub fn font_set_default(&mut self, bytes: &[u8], sdf: bool) -> Result<(), &'static str> {
+        self.render_client.font_replace(&FontToken::default(), bytes, sdf)
+    }
+
+    /// Sets the fonts searched, in order, for characters missing from the given font. Characters
+    /// that aren't in any of the fonts are drawn with the given font's missing glyph. Only the given
+    /// font's list is searched, not the lists of its fallbacks. Passing an empty list removes the
+    /// fallbacks.
+    ///
+    /// For example, a Latin font with Japanese and symbol fonts as fallbacks draws mixed text from
+    /// all three.
+    pub fn font_fallback(&mut self, font: &FontToken, fallbacks: &[FontToken]) {
+        self.render_client.font_fallback(font, fallbacks);
+    }
+
+    /// Unloads a font. Text using the removed font's token is drawn with the engine default font,
+    /// including any font that later replaces it. Text already set on batches keeps its glyphs until
+    /// it's set again.
+    ///
+    /// Returns an error if the token is the engine default font.
+    pub fn font_remove(&mut self, font: &FontToken) -> Result<(), &'static str> {
+        self.render_client.font_remove(font)
+    }
 
     /// Sets the text to render for a given batch. If the token references an invalid or removed
     /// batch, this will panic.
@@ -157,6 +384,23 @@ impl Engine {
         self.render_client.string_set(batch, descs);
     }
 
+    /// Measures the given text without drawing it. The text is laid out exactly as text_set would
+    /// lay it out, and the result includes its bounding box, line count, the bounds of each glyph,
+    /// and the caret positions between characters.
+    pub fn text_measure(&mut self, desc: &Text) -> TextMetrics {
+        self.render_client.string_measure(desc)
+    }
+
+    /// Rasterizes the characters of the given font at each of the given sizes ahead of time, so
+    /// the first frame showing them doesn't stall on rasterization. Useful during loading screens.
+    ///
+    /// Glyphs share a fixed region of the atlas, and the least recently used glyphs not currently
+    /// set on a batch are evicted to make room for new ones, so pre-warm close to when the text
+    /// is needed.
+    pub fn text_prewarm(&mut self, font: &FontToken, chars: &str, sizes: &[u32]) {
+        self.render_client.string_prewarm(font, chars, sizes);
+    }
+
     /// Clears all text from the given batch. This does the same thing as passing an empty Vec to
     /// sprite_set. If the token references an invalid or removed batch, this will panic.
     pub fn text_clear(&mut self, batch: &BatchToken) {
@@ -167,8 +411,8 @@ impl Engine {
     // Texture
     // ////////////////////////////////////////////////////////
 
-    /// Loads a new texture from a given path. If there is an issue loading the texture, this
-    /// function will panic.
+    /// Loads a new texture from a given path. If there is an issue decoding the texture, this function
+    /// will panic.
     pub fn texture_load<P: AsRef<Path>>(&mut self, path: P, format: TextureFormat) -> Result<Texture, &str> {
         if let Ok(f) = File::open(path) {
             let reader = BufReader::new(f);
@@ -178,6 +422,17 @@ impl Engine {
         }
     }
 
+    /// Loads a new texture from a given path. The format is detected from the contents of the file.
+    /// If there is an issue decoding the texture, this function will panic.
+    pub fn texture_load_auto<P: AsRef<Path>>(&mut self, path: P) -> Result<Texture, &str> {
+        let bytes = read_file(path)?;
+        if let Some(format) = TextureFormat::detect(&bytes) {
+            Ok(self.render_client.texture_create(bytes.as_slice(), format))
+        } else {
+            Err("Unable to detect texture format.")
+        }
+    }
+
     /// Loads a new texture from an in memory source. If there is an issue loading the texture, this
     /// function will panic.
     ///
@@ -187,6 +442,105 @@ impl Engine {
         self.render_client.texture_create(reader, format)
     }
 
+    /// Loads a new standalone texture from a given path. The format is detected from the contents
+    /// of the file. Standalone textures are stored outside of the texture atlas with their own
+    /// sampling settings, and are drawn by assigning them to a batch through BatchSettings. If there
+    /// is an issue decoding the texture, this function will panic.
+    ///
+    /// Returns an error if the texture is 4096 pixels or larger along either axis.
+    pub fn texture_load_standalone<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        settings: &TextureSettings,
+    ) -> Result<TextureToken, &str> {
+        let bytes = read_file(path)?;
+        if let Some(format) = TextureFormat::detect(&bytes) {
+            self.render_client.texture_create_standalone(bytes.as_slice(), format, settings)
+        } else {
+            Err("Unable to detect texture format.")
+        }
+    }
+
+    /// Loads a new standalone texture from an in memory source. Standalone textures are stored
+    /// outside of the texture atlas with their own sampling settings, and are drawn by assigning
+    /// them to a batch through BatchSettings. If there is an issue decoding the texture, this
+    /// function will panic.
+    ///
+    /// Returns an error if the texture is 4096 pixels or larger along either axis.
+    pub fn texture_create_standalone<R: Read>(
+        &mut self,
+        reader: R,
+        format: TextureFormat,
+        settings: &TextureSettings,
+    ) -> Result<TextureToken, &'static str> {
+        self.render_client.texture_create_standalone(reader, format, settings)
+    }
+
+    /// Removes a standalone texture, freeing its memory on the GPU. Batches still assigned the
+    /// texture skip drawing their sprites until they're given another. The token must not be used
+    /// after this call.
+    pub fn texture_remove(&mut self, texture: &TextureToken) {
+        self.render_client.texture_remove(texture);
+    }
+
+    /// Creates a new texture from a buffer of pixels. Pixels are laid out row by row, starting from
+    /// the top left of the texture.
+    ///
+    /// Returns an error if the size is 0, or the number of pixels doesn't equal width * height.
+    pub fn texture_from_pixels(
+        &mut self,
+        pixels: &[RGBA8],
+        width: u32,
+        height: u32,
+    ) -> Result<Texture, &'static str> {
+        self.render_client.texture_create_pixels(pixels, width, height)
+    }
+
+    /// Overwrites the pixels in a region of an existing texture. The region is in pixels, with the
+    /// top left of the texture at 0, 0. Pixels are laid out row by row, starting from the top left
+    /// of the region. This ignores any mirroring on the texture. The change is visible after the
+    /// next window_commit.
+    ///
+    /// Returns an error if the region is empty or outside the bounds of the texture, or the number
+    /// of pixels doesn't equal the width * height of the region.
+    pub fn texture_update(
+        &mut self,
+        texture: &Texture,
+        minx: u16,
+        miny: u16,
+        width: u16,
+        height: u16,
+        pixels: &[RGBA8],
+    ) -> Result<(), &'static str> {
+        self.render_client.texture_update(texture, minx, miny, width, height, pixels)
+    }
+
+    /// Loads a sprite sheet exported from Aseprite or TexturePacker. The texture is loaded from the
+    /// first path, and the JSON metadata describing its frames and animation tags from the second.
+    /// If there is an issue decoding the texture, this function will panic.
+    ///
+    /// Returns an error if either file can't be read, or the metadata is malformed.
+    pub fn texture_load_sprite_sheet<P: AsRef<Path>, J: AsRef<Path>>(
+        &mut self,
+        texture_path: P,
+        json_path: J,
+    ) -> Result<SpriteSheet, &str> {
+        let texture = self.texture_load_auto(texture_path)?;
+        let json = read_file(json_path)?;
+        match std::str::from_utf8(&json) {
+            Ok(json) => SpriteSheet::from_json(&texture, json),
+            Err(_) => Err("Sprite sheet metadata isn't valid UTF-8."),
+        }
+    }
+
+    /// Sets the padding in pixels placed around textures added to the atlas after this call. If
+    /// extrude is true, the edge pixels of each texture are copied into its padding, which prevents
+    /// neighboring textures from bleeding in when sprites are scaled or placed at fractional
+    /// positions. The default is 1 pixel of padding with extrusion.
+    pub fn texture_padding(&mut self, padding: u32, extrude: bool) {
+        self.render_client.texture_padding(padding, extrude);
+    }
+
     // ////////////////////////////////////////////////////////
     // Window
     // ////////////////////////////////////////////////////////
@@ -212,8 +566,100 @@ impl Engine {
     }
 
     /// Commits the queued window, batch, sprite, text, and texture related changes to the renderer.
-    /// This function will not block.
+    /// This function will not block. This also ends the game thread's frame for the profiler.
     pub fn window_commit(&mut self) {
+        self.profiler.push(ProfileThread::Game, profile::frame_end());
+        self.frame_receive();
+        if self.overlay.is_some() {
+            self.profile_overlay_update();
+        }
+        self.audio_client.flush();
         self.render_client.commit();
     }
+
+    // ////////////////////////////////////////////////////////
+    // Profile
+    // ////////////////////////////////////////////////////////
+
+    /// Returns stats of the recent frames on the game and render threads, including the zones
+    /// timed with profile::scope. Game thread frames end at each window_commit.
+    pub fn profile_report(&mut self) -> ProfileReport {
+        self.frame_receive();
+        self.profiler.report()
+    }
+
+    /// Writes the recent frames on the game and render threads to a file in the Chrome trace event
+    /// format, which can be loaded in chrome://tracing or Perfetto to look into hitches after the
+    /// fact. The trace holds the same frames as profile_report, with every zone in them.
+    ///
+    /// Returns an error if the file can't be written.
+    pub fn profile_export<P: AsRef<Path>>(&mut self, path: P) -> Result<(), &str> {
+        self.frame_receive();
+        match fs::write(path, profile::trace(&self.profiler)) {
+            Ok(()) => Ok(()),
+            Err(_) => Err("Unable to write file."),
+        }
+    }
+
+    /// Shows a debug overlay of frame time graphs and zone stats for the game and render threads,
+    /// with its top left corner at the position, in the same units as a batch at the default
+    /// settings. The overlay is drawn in front of sprites with a depth below 0.97. Passing None
+    /// hides the overlay.
+    pub fn profile_overlay(&mut self, position: Option<Vector2<f32>>) {
+        match (position, self.overlay.as_mut()) {
+            (Some(position), Some(overlay)) => overlay.position = position,
+            (Some(position), None) => {
+                let batch = self.render_client.batch_create(&BatchSettings::default());
+                self.overlay = Some(Overlay {
+                    batch,
+                    position,
+                });
+            }
+            (None, _) => {
+                if let Some(overlay) = self.overlay.take() {
+                    self.render_client.batch_remove(&overlay.batch);
+                }
+            }
+        }
+    }
+
+    /// Returns the stats of the last frame the render thread drew, like how many sprites it
+    /// submitted and how many bytes it uploaded. These are only updated as frames are drawn, so
+    /// several calls between draws return the same stats.
+    pub fn render_stats(&mut self) -> RenderStats {
+        self.frame_receive();
+        self.render_stats
+    }
+
+    // Reads the frames sent back by the render thread since the last call.
+    fn frame_receive(&mut self) {
+        while let Some(frame) = self.frame_consumer.try_pop() {
+            self.profiler.push(ProfileThread::Render, frame.profile);
+            self.render_stats = frame.stats;
+            self.render_stats.atlas_occupancy = self.render_client.atlas_occupancy();
+        }
+    }
+
+    fn profile_overlay_update(&mut self) {
+        let report = self.profile_report();
+        if let Some(overlay) = &self.overlay {
+            let (sprites, texts) = overlay.draw(&report);
+            self.render_client.sprite_set(&overlay.batch, &sprites);
+            self.render_client.string_set(&overlay.batch, &texts);
+        }
+    }
+}
+
+/// Reads the whole file at the given path into memory.
+fn read_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, &'static str> {
+    let mut bytes = Vec::new();
+    if let Ok(f) = File::open(path) {
+        let mut reader = BufReader::new(f);
+        if reader.read_to_end(&mut bytes).is_err() {
+            return Err("Unable to read file.");
+        }
+        Ok(bytes)
+    } else {
+        Err("Unable to open file to read path.")
+    }
 }
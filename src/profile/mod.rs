@@ -0,0 +1,12 @@
+mod overlay;
+mod profiler;
+mod recorder;
+mod trace;
+
+pub use crate::profile::profiler::{ProfileReport, ProfileThread, ThreadStats, TimeStats, ZoneStats};
+pub use crate::profile::recorder::{scope, Scope};
+
+pub(crate) use crate::profile::overlay::Overlay;
+pub(crate) use crate::profile::profiler::Profiler;
+pub(crate) use crate::profile::recorder::{frame_end, Frame};
+pub(crate) use crate::profile::trace::trace;
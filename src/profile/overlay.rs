@@ -0,0 +1,122 @@
+use crate::colors::*;
+use crate::profile::profiler::*;
+use crate::types::*;
+use cgmath::*;
+use std::time::Duration;
+
+// Pixels between the edge of the overlay and its contents.
+const PADDING: f32 = 8.0;
+// Height of each frame time graph, in pixels.
+const GRAPH_HEIGHT: f32 = 64.0;
+// Height of each bar in the graph per millisecond of frame time.
+const PIXELS_PER_MILLI: f32 = 2.0;
+const TEXT_SCALE: u32 = 14;
+const LINE_HEIGHT: f32 = 16.0;
+// Most zones listed under each graph.
+const MAX_ZONES: usize = 8;
+// Frame time of 60 frames per second, drawn as a line across each graph.
+const BUDGET: f32 = 1000.0 / 60.0;
+// Depths of the overlay's layers, in front of most sprites.
+const BACKGROUND_DEPTH: f32 = 0.97;
+const GRAPH_DEPTH: f32 = 0.98;
+const TEXT_DEPTH: f32 = 0.99;
+
+fn millis(duration: Duration) -> f32 {
+    duration.as_secs_f32() * 1000.0
+}
+
+fn stats_line(label: &str, time: &TimeStats) -> String {
+    format!(
+        "{}  p50 {:.2}ms  p99 {:.2}ms  max {:.2}ms",
+        label,
+        millis(time.p50),
+        millis(time.p99),
+        millis(time.max)
+    )
+}
+
+/// Draws frame time graphs and zone stats into a batch of its own.
+pub struct Overlay {
+    pub batch: BatchToken,
+    /// Top left corner of the overlay.
+    pub position: Vector2<f32>,
+}
+
+impl Overlay {
+    /// Lays out the report, returning the sprites and text to draw it with.
+    pub fn draw(&self, report: &ProfileReport) -> (Vec<Sprite>, Vec<Text>) {
+        let width = HISTORY as f32;
+        let mut sprites = Vec::new();
+        let mut texts = Vec::new();
+        let mut y = self.position.y - PADDING;
+        for (label, stats) in &[("Game", &report.game), ("Render", &report.render)] {
+            y = self.section(label, stats, y, &mut sprites, &mut texts);
+        }
+        let height = self.position.y - y;
+        let background = Sprite {
+            pos: Vector3::new(self.position.x, y, BACKGROUND_DEPTH),
+            size: Vector2::new((width + PADDING * 2.0) as u16, height as u16),
+            color: RGBA8::new_raw(0, 0, 0, 192),
+            ..Sprite::default()
+        };
+        sprites.insert(0, background);
+        (sprites, texts)
+    }
+
+    // Draws a thread's stats from the top y down, and returns the y below them.
+    fn section(
+        &self,
+        label: &str,
+        stats: &ThreadStats,
+        mut y: f32,
+        sprites: &mut Vec<Sprite>,
+        texts: &mut Vec<Text>,
+    ) -> f32 {
+        let x = self.position.x + PADDING;
+        let mut line = |string: String, y: &mut f32, color: RGBA8| {
+            texts.push(Text {
+                string,
+                pos: Vector3::new(x, *y - LINE_HEIGHT, TEXT_DEPTH),
+                max_height: Some(LINE_HEIGHT),
+                horizontal_align: HorizontalAlign::Left,
+                scale: TEXT_SCALE,
+                color,
+                ..Text::default()
+            });
+            *y -= LINE_HEIGHT;
+        };
+        line(stats_line(label, &stats.frame), &mut y, WHITE);
+
+        // Bars for each frame, colored by how they compare to the budget.
+        let bottom = y - GRAPH_HEIGHT;
+        for (index, &frame) in stats.history.iter().enumerate() {
+            let millis = millis(frame);
+            let color = if millis <= BUDGET {
+                GREEN
+            } else if millis <= BUDGET * 2.0 {
+                YELLOW
+            } else {
+                RED
+            };
+            sprites.push(Sprite {
+                pos: Vector3::new(x + index as f32, bottom, GRAPH_DEPTH),
+                size: Vector2::new(1, (millis * PIXELS_PER_MILLI).clamp(1.0, GRAPH_HEIGHT) as u16),
+                color,
+                ..Sprite::default()
+            });
+        }
+        sprites.push(Sprite {
+            pos: Vector3::new(x, bottom + BUDGET * PIXELS_PER_MILLI, GRAPH_DEPTH),
+            size: Vector2::new(HISTORY as u16, 1),
+            color: RGBA8::new_raw(255, 255, 255, 96),
+            ..Sprite::default()
+        });
+        y = bottom - PADDING / 2.0;
+
+        for zone in stats.zones.iter().take(MAX_ZONES) {
+            let label = format!("{}{} x{:.1}", "  ".repeat(zone.depth + 1), zone.name, zone.calls);
+            line(stats_line(&label, &zone.time), &mut y, RGBA8::new_raw(200, 200, 200, 255));
+        }
+        y - PADDING
+    }
+}
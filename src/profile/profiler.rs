@@ -0,0 +1,243 @@
+use crate::profile::recorder::*;
+use hashbrown::HashMap;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+// Frames kept in each thread's history.
+pub const HISTORY: usize = 240;
+
+/// A thread the engine profiles.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProfileThread {
+    /// The thread running the game loop, where frames end at window_commit.
+    Game,
+    /// The thread drawing each committed frame.
+    Render,
+}
+
+/// Summary of a set of times.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct TimeStats {
+    pub mean: Duration,
+    /// Median time.
+    pub p50: Duration,
+    /// Time that 99% of the times are within.
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl TimeStats {
+    fn new(samples: &mut [Duration]) -> TimeStats {
+        if samples.is_empty() {
+            return TimeStats::default();
+        }
+        samples.sort();
+        let percentile = |p: f64| samples[((samples.len() - 1) as f64 * p).round() as usize];
+        TimeStats {
+            mean: samples.iter().sum::<Duration>() / samples.len() as u32,
+            p50: percentile(0.5),
+            p99: percentile(0.99),
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// Stats of a zone over the frames in the history.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZoneStats {
+    pub name: &'static str,
+    /// How many zones this is nested in.
+    pub depth: usize,
+    /// Average number of times the zone ran per frame.
+    pub calls: f32,
+    /// Time spent in the zone per frame, over the frames it ran in.
+    pub time: TimeStats,
+}
+
+/// Stats of a thread over the frames in its history.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ThreadStats {
+    /// Time from the end of one frame to the end of the next.
+    pub frame: TimeStats,
+    /// Length of each frame in the history, oldest first.
+    pub history: Vec<Duration>,
+    /// Zones in the order they're nested, parents before their children.
+    pub zones: Vec<ZoneStats>,
+}
+
+/// Stats of the recent frames on each thread.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProfileReport {
+    pub game: ThreadStats,
+    pub render: ThreadStats,
+}
+
+/// Keeps a history of the recent frames on each thread.
+pub struct Profiler {
+    game: VecDeque<Frame>,
+    render: VecDeque<Frame>,
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler {
+            game: VecDeque::with_capacity(HISTORY),
+            render: VecDeque::with_capacity(HISTORY),
+        }
+    }
+
+    pub fn frames(&self, thread: ProfileThread) -> &VecDeque<Frame> {
+        match thread {
+            ProfileThread::Game => &self.game,
+            ProfileThread::Render => &self.render,
+        }
+    }
+
+    pub fn push(&mut self, thread: ProfileThread, frame: Frame) {
+        let frames = match thread {
+            ProfileThread::Game => &mut self.game,
+            ProfileThread::Render => &mut self.render,
+        };
+        if frames.len() == HISTORY {
+            frames.pop_front();
+        }
+        frames.push_back(frame);
+    }
+
+    pub fn report(&self) -> ProfileReport {
+        ProfileReport {
+            game: Self::stats(&self.game),
+            render: Self::stats(&self.render),
+        }
+    }
+
+    fn stats(frames: &VecDeque<Frame>) -> ThreadStats {
+        let history: Vec<Duration> = frames.iter().map(|frame| frame.end - frame.start).collect();
+        // Each zone is identified by its path of names from the outermost zone, so zones with the
+        // same name under different parents are kept apart.
+        let mut order: HashMap<Vec<&'static str>, usize> = HashMap::new();
+        let mut zones: Vec<(Vec<&'static str>, usize, Vec<Duration>)> = Vec::new();
+        for frame in frames {
+            let mut paths: Vec<Vec<&'static str>> = Vec::with_capacity(frame.zones.len());
+            let mut totals: HashMap<usize, Duration> = HashMap::new();
+            for zone in &frame.zones {
+                let mut path = match zone.parent {
+                    Some(parent) => paths[parent].clone(),
+                    None => Vec::new(),
+                };
+                path.push(zone.name);
+                let index = match order.get(&path) {
+                    Some(&index) => index,
+                    None => {
+                        order.insert(path.clone(), zones.len());
+                        zones.push((path.clone(), 0, Vec::new()));
+                        zones.len() - 1
+                    }
+                };
+                zones[index].1 += 1;
+                *totals.entry(index).or_insert(Duration::from_secs(0)) += zone.end - zone.start;
+                paths.push(path);
+            }
+            for (index, total) in totals {
+                zones[index].2.push(total);
+            }
+        }
+        // Sorting by the order each level of the path was first seen puts children right after
+        // their parents.
+        let mut keys: Vec<(Vec<usize>, usize)> = zones
+            .iter()
+            .enumerate()
+            .map(|(index, (path, _, _))| {
+                let key = (1..=path.len()).map(|length| order[&path[..length]]).collect();
+                (key, index)
+            })
+            .collect();
+        keys.sort();
+        let count = frames.len().max(1) as f32;
+        ThreadStats {
+            frame: TimeStats::new(&mut history.clone()),
+            history,
+            zones: keys
+                .into_iter()
+                .map(|(_, index)| {
+                    let (path, calls, times) = &mut zones[index];
+                    ZoneStats {
+                        name: path[path.len() - 1],
+                        depth: path.len() - 1,
+                        calls: *calls as f32 / count,
+                        time: TimeStats::new(times),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+    use std::time::Instant;
+
+    fn zone(name: &'static str, parent: Option<usize>, start: Instant, millis: u64) -> Zone {
+        Zone {
+            name,
+            parent,
+            depth: if parent.is_some() {
+                1
+            } else {
+                0
+            },
+            start,
+            end: start + Duration::from_millis(millis),
+        }
+    }
+
+    #[test]
+    fn report() {
+        let mut profiler = Profiler::new();
+        let start = Instant::now();
+        for index in 0..300u64 {
+            let frame_start = start + Duration::from_millis(index * 20);
+            let mut zones =
+                vec![zone("update", None, frame_start, 10), zone("physics", Some(0), frame_start, 4)];
+            if index % 2 == 0 {
+                zones.push(zone("draw", None, frame_start, 2));
+                zones.push(zone("physics", Some(2), frame_start, 1));
+                zones.push(zone("physics", Some(2), frame_start, 1));
+            }
+            // Every hundredth frame is slow.
+            let length = if index % 100 == 0 {
+                100
+            } else {
+                10 + index % 10
+            };
+            profiler.push(
+                ProfileThread::Game,
+                Frame {
+                    start: frame_start,
+                    end: frame_start + Duration::from_millis(length),
+                    zones,
+                },
+            );
+        }
+        let report = profiler.report();
+        let game = report.game;
+        assert_eq!(game.history.len(), HISTORY);
+        assert_eq!(game.frame.p50, Duration::from_millis(15));
+        assert_eq!(game.frame.p99, Duration::from_millis(19));
+        assert_eq!(game.frame.max, Duration::from_millis(100));
+        assert!(report.render.zones.is_empty());
+
+        let zones: Vec<_> = game.zones.iter().map(|zone| (zone.name, zone.depth, zone.calls)).collect();
+        assert_eq!(
+            zones,
+            vec![("update", 0, 1.0), ("physics", 1, 1.0), ("draw", 0, 0.5), ("physics", 1, 1.0)]
+        );
+        assert_eq!(game.zones[3].time.p50, Duration::from_millis(2));
+    }
+}
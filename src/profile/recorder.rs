@@ -0,0 +1,152 @@
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::mem;
+use std::time::Instant;
+
+/// A timed zone of a frame.
+#[derive(Clone, Debug)]
+pub struct Zone {
+    pub name: &'static str,
+    /// Index of the zone this one is nested in, which is always earlier in the frame.
+    pub parent: Option<usize>,
+    pub depth: usize,
+    pub start: Instant,
+    pub end: Instant,
+}
+
+/// The zones recorded on a thread over a frame.
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub start: Instant,
+    pub end: Instant,
+    pub zones: Vec<Zone>,
+}
+
+struct Recorder {
+    start: Instant,
+    zones: Vec<Zone>,
+    // Indices of the zones still open, innermost last.
+    open: Vec<usize>,
+}
+
+impl Recorder {
+    fn new(start: Instant) -> Recorder {
+        Recorder {
+            start,
+            zones: Vec::new(),
+            open: Vec::new(),
+        }
+    }
+
+    fn open(&mut self, name: &'static str) {
+        let now = Instant::now();
+        self.zones.push(Zone {
+            name,
+            parent: self.open.last().copied(),
+            depth: self.open.len(),
+            start: now,
+            end: now,
+        });
+        self.open.push(self.zones.len() - 1);
+    }
+
+    fn close(&mut self) {
+        if let Some(index) = self.open.pop() {
+            self.zones[index].end = Instant::now();
+        }
+    }
+
+    // Ends the frame. Zones still open are cut at the end of the frame, and carry on in the next.
+    fn end(&mut self) -> Frame {
+        let now = Instant::now();
+        let mut next = Recorder::new(now);
+        for &index in &self.open {
+            let zone = &mut self.zones[index];
+            zone.end = now;
+            next.zones.push(Zone {
+                name: zone.name,
+                parent: next.open.last().copied(),
+                depth: zone.depth,
+                start: now,
+                end: now,
+            });
+            next.open.push(next.zones.len() - 1);
+        }
+        let frame = Frame {
+            start: self.start,
+            end: now,
+            zones: mem::take(&mut self.zones),
+        };
+        *self = next;
+        frame
+    }
+}
+
+thread_local! {
+    static RECORDER: RefCell<Recorder> = RefCell::new(Recorder::new(Instant::now()));
+}
+
+/// Starts timing a zone of the current frame on this thread, until the returned scope is dropped.
+/// Zones opened while another is open are nested inside it. Zones on the game thread are grouped
+/// into frames by window_commit.
+pub fn scope(name: &'static str) -> Scope {
+    RECORDER.with(|recorder| recorder.borrow_mut().open(name));
+    Scope {
+        _marker: PhantomData,
+    }
+}
+
+/// A zone being timed. The zone ends when this is dropped.
+#[must_use]
+pub struct Scope {
+    // Zones are recorded per thread, so scopes stay on the thread that opened them.
+    _marker: PhantomData<*const ()>,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        RECORDER.with(|recorder| recorder.borrow_mut().close());
+    }
+}
+
+/// Ends the current frame on this thread, and returns the zones recorded in it.
+pub fn frame_end() -> Frame {
+    RECORDER.with(|recorder| recorder.borrow_mut().end())
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn nesting() {
+        frame_end();
+        {
+            let _update = scope("update");
+            {
+                let _physics = scope("physics");
+            }
+            let _ai = scope("ai");
+        }
+        let _draw = scope("draw");
+        let frame = frame_end();
+        let names: Vec<_> = frame.zones.iter().map(|zone| (zone.name, zone.parent, zone.depth)).collect();
+        assert_eq!(
+            names,
+            vec![("update", None, 0), ("physics", Some(0), 1), ("ai", Some(0), 1), ("draw", None, 0)]
+        );
+        assert!(frame.zones[0].start <= frame.zones[1].start && frame.zones[2].end <= frame.zones[0].end);
+
+        // Open zones carry on into the next frame.
+        assert_eq!(frame.zones[3].end, frame.end);
+        drop(_draw);
+        let frame = frame_end();
+        assert_eq!(frame.zones.len(), 1);
+        assert_eq!(frame.zones[0].name, "draw");
+    }
+}
@@ -0,0 +1,115 @@
+use crate::profile::profiler::*;
+use serde_json::{json, Value};
+use std::time::Instant;
+
+// Microseconds from the origin to the instant, the unit of trace timestamps.
+fn micros(origin: Instant, instant: Instant) -> f64 {
+    (instant - origin).as_nanos() as f64 / 1000.0
+}
+
+/// Writes the frames in the profiler's history as Chrome trace events, which chrome://tracing and
+/// Perfetto can load. Each thread gets its own track, with an event for every frame and zone.
+pub fn trace(profiler: &Profiler) -> String {
+    let threads = [(ProfileThread::Game, 1, "Game"), (ProfileThread::Render, 2, "Render")];
+    let origin = threads
+        .iter()
+        .filter_map(|&(thread, _, _)| profiler.frames(thread).front().map(|frame| frame.start))
+        .min()
+        .unwrap_or_else(Instant::now);
+    let mut events: Vec<Value> = Vec::new();
+    for &(thread, tid, name) in &threads {
+        events.push(json!({
+            "name": "thread_name",
+            "ph": "M",
+            "pid": 1,
+            "tid": tid,
+            "args": { "name": name },
+        }));
+        for (index, frame) in profiler.frames(thread).iter().enumerate() {
+            events.push(event(origin, tid, "frame", "frame", frame.start, frame.end, index));
+            for zone in &frame.zones {
+                events.push(event(origin, tid, zone.name, "zone", zone.start, zone.end, index));
+            }
+        }
+    }
+    json!({
+        "traceEvents": events,
+        "displayTimeUnit": "ms",
+    })
+    .to_string()
+}
+
+// A complete event, which has both its start and duration.
+fn event(
+    origin: Instant,
+    tid: u32,
+    name: &str,
+    category: &str,
+    start: Instant,
+    end: Instant,
+    frame: usize,
+) -> Value {
+    json!({
+        "name": name,
+        "cat": category,
+        "ph": "X",
+        "ts": micros(origin, start),
+        "dur": micros(start, end),
+        "pid": 1,
+        "tid": tid,
+        "args": { "frame": frame },
+    })
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+    use crate::profile::recorder::*;
+    use std::time::Duration;
+
+    #[test]
+    fn chrome_trace() {
+        let mut profiler = Profiler::new();
+        let start = Instant::now();
+        let millis = |millis: u64| start + Duration::from_millis(millis);
+        profiler.push(
+            ProfileThread::Render,
+            Frame {
+                start: millis(2),
+                end: millis(10),
+                zones: vec![Zone {
+                    name: "draw",
+                    parent: None,
+                    depth: 0,
+                    start: millis(3),
+                    end: millis(5),
+                }],
+            },
+        );
+        profiler.push(
+            ProfileThread::Game,
+            Frame {
+                start: millis(1),
+                end: millis(17),
+                zones: Vec::new(),
+            },
+        );
+
+        let trace: Value = serde_json::from_str(&trace(&profiler)).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[0]["ph"], "M");
+        assert_eq!(events[0]["args"]["name"], "Game");
+        assert_eq!(events[1]["ts"], 0.0);
+        assert_eq!(events[1]["dur"], 16000.0);
+        assert_eq!(events[4]["name"], "draw");
+        assert_eq!(events[4]["tid"], 2);
+        assert_eq!(events[4]["ts"], 2000.0);
+        assert_eq!(events[4]["dur"], 2000.0);
+    }
+}
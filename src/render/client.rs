@@ -7,11 +7,15 @@ use crate::utility::unordered_tracker::*;
 use std::io::Read;
 use std::ptr;
 
+// Standalone texture UVs use the same units as the atlas, so they share its maximum size.
+const MAX_STANDALONE_SIZE: u32 = 4096;
+
 pub struct RenderClient {
     render_producer: swap_spsc::Producer<RenderState>,
     atlas: TextureAtlas,
     text_cache: TextCache,
     batch_tracker: UnorderedTracker<BatchToken>,
+    texture_count: usize,
 }
 
 impl RenderClient {
@@ -21,6 +25,7 @@ impl RenderClient {
             atlas: TextureAtlas::new(),
             text_cache: TextCache::new(),
             batch_tracker: UnorderedTracker::new(),
+            texture_count: 0,
         }
     }
 
@@ -40,6 +45,7 @@ impl RenderClient {
 
     pub fn batch_remove(&mut self, batch: &BatchToken) {
         let batch_index = self.batch_tracker.remove(batch.key());
+        self.text_cache.release(batch.key().slot());
         let state = self.render_producer.get();
         state.batches.swap_remove(batch_index);
         state.batch_changes.push(BatchMessage::Remove {
@@ -85,27 +91,82 @@ impl RenderClient {
     // String
     // ////////////////////////////////////////////////////////
 
-    pub fn font_create(&mut self, path: &str) -> FontToken {
-        FontToken::new(self.text_cache.add_font_path(path))
+    pub fn font_create(&mut self, bytes: &[u8], sdf: bool) -> Result<FontToken, &'static str> {
+        Ok(FontToken::new(self.text_cache.add_font_bytes(bytes, sdf)?))
+    }
+
+    pub fn font_create_bitmap(
+        &mut self,
+        mut font: BitmapFont,
+        pages: &[Vec<u8>],
+    ) -> Result<FontToken, &'static str> {
+        let mut images = Vec::with_capacity(pages.len());
+        for bytes in pages {
+            match TextureFormat::detect(bytes) {
+                Some(format) => images.push(Image::from_raw(bytes.as_slice(), format)),
+                None => Err("Unable to detect the texture format of a bitmap font page.")?,
+            }
+        }
+        // Pages are only added once they've all been read, so a bad page doesn't leak the others.
+        let uvs = images.into_iter().map(|image| self.atlas.add(image)).collect();
+        font.set_page_uvs(uvs);
+        Ok(FontToken::new(self.text_cache.add_bitmap_font(font)))
+    }
+
+    pub fn font_replace(&mut self, font: &FontToken, bytes: &[u8], sdf: bool) -> Result<(), &'static str> {
+        self.text_cache.replace_font_bytes(font.key(), bytes, sdf)
+    }
+
+    pub fn font_fallback(&mut self, font: &FontToken, fallbacks: &[FontToken]) {
+        let fallbacks: Vec<usize> = fallbacks.iter().map(|fallback| fallback.key()).collect();
+        self.text_cache.set_fallbacks(font.key(), &fallbacks);
+    }
+
+    pub fn font_remove(&mut self, font: &FontToken) -> Result<(), &'static str> {
+        if *font == FontToken::default() {
+            Err("The default font can't be removed.")?
+        }
+        self.text_cache.remove_font(font.key());
+        Ok(())
     }
 
     pub fn string_set(&mut self, batch: &BatchToken, descs: &Vec<Text>) {
-        let batch_index = self.batch_tracker.get(batch.key());
+        let batch_key = batch.key();
+        let batch_index = self.batch_tracker.get(batch_key);
         let state = self.render_producer.get();
         let batch = &mut state.batches[batch_index];
         batch.dirty_strings = true;
         unsafe { batch.strings.set_len(0) };
+        unsafe { batch.sdf_strings.set_len(0) };
+        let owner = batch_key.slot();
+        self.text_cache.release(owner);
         for desc in descs {
-            self.text_cache.rasterize(&mut self.atlas, desc, &mut batch.strings);
+            self.text_cache.rasterize(
+                &mut self.atlas,
+                desc,
+                owner,
+                &mut batch.strings,
+                &mut batch.sdf_strings,
+            );
         }
     }
 
+    pub fn string_prewarm(&mut self, font: &FontToken, chars: &str, sizes: &[u32]) {
+        self.text_cache.prewarm(&mut self.atlas, font.key(), chars, sizes);
+    }
+
+    pub fn string_measure(&self, desc: &Text) -> TextMetrics {
+        self.text_cache.measure(desc)
+    }
+
     pub fn string_clear(&mut self, batch: &BatchToken) {
         let batch_index = self.batch_tracker.get(batch.key());
+        self.text_cache.release(batch.key().slot());
         let state = self.render_producer.get();
         let batch = &mut state.batches[batch_index];
         batch.dirty_strings = true;
         unsafe { batch.strings.set_len(0) };
+        unsafe { batch.sdf_strings.set_len(0) };
     }
 
     // ////////////////////////////////////////////////////////
@@ -118,6 +179,84 @@ impl RenderClient {
         Texture(uv)
     }
 
+    pub fn texture_create_standalone<R: Read>(
+        &mut self,
+        bytes: R,
+        format: TextureFormat,
+        settings: &TextureSettings,
+    ) -> Result<TextureToken, &'static str> {
+        let image = Image::from_raw(bytes, format);
+        if image.width() >= MAX_STANDALONE_SIZE || image.height() >= MAX_STANDALONE_SIZE {
+            Err("Standalone textures must be smaller than 4096x4096.")?
+        }
+        let token = TextureToken::new(self.texture_count, image.width() as u16, image.height() as u16);
+        self.texture_count += 1;
+        let state = self.render_producer.get();
+        state.texture_changes.push(TextureMessage::Create {
+            image,
+            settings: *settings,
+        });
+        Ok(token)
+    }
+
+    pub fn texture_remove(&mut self, texture: &TextureToken) {
+        let state = self.render_producer.get();
+        state.texture_changes.push(TextureMessage::Remove {
+            key: texture.key(),
+        });
+    }
+
+    pub fn texture_create_pixels(
+        &mut self,
+        pixels: &[RGBA8],
+        width: u32,
+        height: u32,
+    ) -> Result<Texture, &'static str> {
+        if width == 0 || height == 0 {
+            Err("Size must be greater than 0")?
+        }
+        if pixels.len() != (width * height) as usize {
+            Err("The number of pixels must equal width * height.")?
+        }
+        let image = Image::from_vec(pixels.to_vec(), width, height);
+        let uv = self.atlas.add(image);
+        Ok(Texture(uv))
+    }
+
+    pub fn texture_update(
+        &mut self,
+        texture: &Texture,
+        minx: u16,
+        miny: u16,
+        width: u16,
+        height: u16,
+        pixels: &[RGBA8],
+    ) -> Result<(), &'static str> {
+        if width == 0 || height == 0 {
+            Err("Size must be greater than 0")?
+        }
+        if minx as u32 + width as u32 > texture.width() as u32
+            || miny as u32 + height as u32 > texture.height() as u32
+        {
+            Err("Requested region is outside the bounds of the texture.")?
+        }
+        if pixels.len() != width as usize * height as usize {
+            Err("The number of pixels must equal the size of the region.")?
+        }
+        let region = texture.sub_texture(minx, miny, width, height)?;
+        let image = Image::from_vec(pixels.to_vec(), width as u32, height as u32);
+        self.atlas.update(region.0, &image);
+        Ok(())
+    }
+
+    pub fn texture_padding(&mut self, padding: u32, extrude: bool) {
+        self.atlas.set_padding(padding, extrude);
+    }
+
+    pub fn atlas_occupancy(&self) -> f32 {
+        self.atlas.occupancy()
+    }
+
     // ////////////////////////////////////////////////////////
     // Window
     // ////////////////////////////////////////////////////////
@@ -146,6 +285,7 @@ impl RenderClient {
         let state = self.render_producer.get();
         state.atlas = self.atlas.sync();
         if self.render_producer.try_next() {
+            self.atlas.flush();
             let state = self.render_producer.get();
             while state.batches.len() < self.batch_tracker.len() {
                 state.batches.push(BatchState::default());
@@ -121,6 +121,7 @@ pub enum BlendFactor {
 #[derive(Copy, Clone)]
 pub enum TextureUnit {
     Atlas = glow::TEXTURE0,
+    Standalone = glow::TEXTURE1,
 }
 
 #[repr(u32)]
@@ -519,6 +520,10 @@ impl OpenGL {
         }
     }
 
+    pub fn uniform_2f(&self, location: Option<&resource::UniformLocation>, x: f32, y: f32) {
+        unsafe { self.gl.uniform_2_f32(location, x, y) };
+    }
+
     pub fn uniform_1i(&self, location: Option<&resource::UniformLocation>, x: i32) {
         unsafe { self.gl.uniform_1_i32(location, x) };
     }
@@ -632,6 +637,41 @@ impl OpenGL {
         };
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn tex_sub_image_2d<T: Sized>(
+        &self,
+        target: TextureLoadTarget,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: PixelFormat,
+        ty: PixelType,
+        pixels: &[T],
+    ) {
+        unsafe {
+            let len = core::mem::size_of_val(pixels);
+            let ptr = pixels.as_ptr() as *const u8;
+            let slice = core::slice::from_raw_parts(ptr, len);
+            self.gl.tex_sub_image_2d(
+                target as u32,
+                level,
+                x_offset,
+                y_offset,
+                width,
+                height,
+                format as u32,
+                ty as u32,
+                glow::PixelUnpackData::Slice(slice),
+            )
+        };
+    }
+
+    pub fn generate_mipmap(&self, target: TextureBindingTarget) {
+        unsafe { self.gl.generate_mipmap(target as u32) };
+    }
+
     pub fn tex_parameter_wrap_s(&self, target: TextureParameterTarget, value: TextureWrapValue) {
         unsafe {
             self.gl.tex_parameter_i32(target as u32, TextureParameterName::TextureWrapS as u32, value as i32)
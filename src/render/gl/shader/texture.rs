@@ -18,6 +18,8 @@ out vec2 v_uv;
 out vec4 v_color;
 
 uniform mat4 ortho;
+// Scale applied to the UVs, used to measure UVs in pixels of a standalone texture.
+uniform vec2 uv_scale = vec2(1.0, 1.0);
 
 // UV Layout: xmin xmax ymin ymax
 // ymin and ymax are swapped below because OpenGL reads images from bottom row to top row, but
@@ -48,7 +50,7 @@ vec4 rotateZ(vec3 pos) {
 
 void main() {
     vec4 temp = a_uv * uv_lut[gl_VertexID];
-    v_uv = vec2(temp.x + temp.y, temp.z + temp.w);
+    v_uv = vec2(temp.x + temp.y, temp.z + temp.w) * uv_scale;
     v_color = a_color;
 
     vec3 size = vec3(a_size * pos_lut[gl_VertexID], 0.0);
@@ -64,9 +66,31 @@ in vec4 v_color;
 out vec4 a_color;
 
 uniform sampler2D tex[1];
+// If the texture holds signed distance fields in its alpha channel, with the edge at 0.5.
+uniform bool sdf = false;
+
+// The atlas is sampled with nearest filtering, so distance fields are interpolated by hand.
+float sample_distance(vec2 uv) {
+    vec2 texel = uv * vec2(textureSize(tex[0], 0)) - 0.5;
+    ivec2 base = ivec2(floor(texel));
+    vec2 f = fract(texel);
+    float a = texelFetch(tex[0], base, 0).a;
+    float b = texelFetch(tex[0], base + ivec2(1, 0), 0).a;
+    float c = texelFetch(tex[0], base + ivec2(0, 1), 0).a;
+    float d = texelFetch(tex[0], base + ivec2(1, 1), 0).a;
+    return mix(mix(a, b, f.x), mix(c, d, f.x), f.y);
+}
 
 void main() {
-    a_color = texture(tex[0], v_uv) * v_color;
+    if (sdf) {
+        float dist = sample_distance(v_uv);
+        // Keeps the edge about a pixel wide on screen at any scale.
+        float width = max(fwidth(dist) * 0.5, 0.0001);
+        float alpha = smoothstep(0.5 - width, 0.5 + width, dist);
+        a_color = vec4(v_color.rgb, v_color.a * alpha);
+    } else {
+        a_color = texture(tex[0], v_uv) * v_color;
+    }
     if (a_color.a <= 0.0) {
         discard;
     }
@@ -78,6 +102,8 @@ pub struct TextureShader {
     program: ShaderProgram,
     uniform_ortho: resource::UniformLocation,
     uniform_texture: resource::UniformLocation,
+    uniform_uv_scale: resource::UniformLocation,
+    uniform_sdf: resource::UniformLocation,
 }
 
 impl TextureShader {
@@ -85,11 +111,15 @@ impl TextureShader {
         let program = ShaderProgram::new(gl.clone(), VERTEX, FRAGMENT);
         let uniform_ortho = program.get_uniform_location("ortho");
         let uniform_texture = program.get_uniform_location("tex[0]");
+        let uniform_uv_scale = program.get_uniform_location("uv_scale");
+        let uniform_sdf = program.get_uniform_location("sdf");
         TextureShader {
             gl,
             program,
             uniform_ortho,
             uniform_texture,
+            uniform_uv_scale,
+            uniform_sdf,
         }
     }
 
@@ -102,6 +132,16 @@ impl TextureShader {
         self.gl.uniform_matrix_4fv(Some(&self.uniform_ortho), false, matrix.as_ref());
     }
 
+    /// Updates the UV scale uniform in the shader.
+    pub fn uv_scale(&self, scale: Vector2<f32>) {
+        self.gl.uniform_2f(Some(&self.uniform_uv_scale), scale.x, scale.y);
+    }
+
+    /// Updates the distance field uniform in the shader.
+    pub fn sdf(&self, sdf: bool) {
+        self.gl.uniform_1i(Some(&self.uniform_sdf), sdf as i32);
+    }
+
     /// Updates the texture uniform in the shader.
     pub fn texture(&self, unit: TextureUnit) {
         let unit = (unit as u32 - TextureUnit::Atlas as u32) as i32;
@@ -8,23 +8,94 @@ use crate::render::gl::window::*;
 use crate::texture::*;
 use crate::types::*;
 use cgmath::*;
+use std::mem;
 
 struct Batch {
     desc: BatchSettings,
     sprites: Buffer<Sprite>,
     strings: Buffer<Sprite>,
+    sdf_strings: Buffer<Sprite>,
     matrix_transform: Matrix4<f32>,
     matrix_full: Matrix4<f32>,
 }
 
+impl Batch {
+    // Describes what drawing the batch involves, given the loaded standalone textures.
+    fn draw_info(&self, textures: &[Option<TextureHandle>]) -> BatchDraw {
+        BatchDraw {
+            visible: self.desc.visible,
+            sprites: self.sprites.len(),
+            glyphs: self.strings.len(),
+            sdf_glyphs: self.sdf_strings.len(),
+            textured: match self.desc.texture {
+                Some(token) => textures[token.key()].is_some(),
+                None => true,
+            },
+        }
+    }
+}
+
+// What drawing a batch involves, which both the draw and the stats are decided from.
+#[derive(Copy, Clone, Debug, Default)]
+struct BatchDraw {
+    visible: bool,
+    sprites: usize,
+    glyphs: usize,
+    sdf_glyphs: usize,
+    // If the sprites have a texture to draw with. Sprites drawing a removed texture are skipped.
+    textured: bool,
+}
+
+impl BatchDraw {
+    fn draws_sprites(&self) -> bool {
+        self.visible && self.sprites > 0 && self.textured
+    }
+
+    fn draws_glyphs(&self) -> bool {
+        self.visible && self.glyphs > 0
+    }
+
+    fn draws_sdf_glyphs(&self) -> bool {
+        self.visible && self.sdf_glyphs > 0
+    }
+}
+
+// Adds drawing the batches to the stats.
+fn stats_draw<I: IntoIterator<Item = BatchDraw>>(stats: &mut RenderStats, draws: I) {
+    for draw in draws.into_iter().filter(|draw| draw.visible) {
+        let glyphs = draw.glyphs + draw.sdf_glyphs;
+        if draw.sprites + glyphs > 0 {
+            stats.batches += 1;
+        }
+        stats.sprites += draw.sprites;
+        stats.glyphs += glyphs;
+        stats.draw_calls +=
+            draw.draws_sprites() as usize + draw.draws_glyphs() as usize + draw.draws_sdf_glyphs() as usize;
+    }
+}
+
+// Adds uploading the quads to a buffer to the stats.
+fn stats_buffer(stats: &mut RenderStats, quads: &[Sprite]) {
+    stats.buffer_bytes += mem::size_of_val(quads);
+}
+
+// Adds uploading the pixels to a texture to the stats.
+fn stats_texture(stats: &mut RenderStats, pixels: &[RGBA8]) {
+    stats.texture_bytes += mem::size_of_val(pixels);
+}
+
 pub struct OpenGLState {
     window: OpenGLWindow,
     gl: OpenGL,
     shader: TextureShader,
     texture_atlas: TextureHandle,
+    // Standalone textures by key. Removed textures leave None so later keys stay valid.
+    textures: Vec<Option<TextureHandle>>,
     batches: Vec<Batch>,
     matrix_bounds: Matrix4<f32>,
     current_logical_size: Vector2<f32>,
+    // Work done since the stats were last taken.
+    stats: RenderStats,
 }
 
 fn matrix_from_bounds(bounds: &Vector2<f32>) -> Matrix4<f32> {
@@ -45,9 +116,11 @@ impl OpenGLState {
             gl,
             shader,
             texture_atlas,
+            textures: Vec::new(),
             batches: Vec::new(),
             matrix_bounds: matrix_from_bounds(&logical_size),
             current_logical_size: Vector2::zero(),
+            stats: RenderStats::default(),
         };
         // Bind shader once.
         state.shader.bind();
@@ -81,8 +154,20 @@ impl OpenGLState {
         self.window.set_vsync(vsync);
     }
 
-    pub fn upload_texture_atlas(&mut self, texture: &Image) {
-        self.texture_atlas.set_texture(texture);
+    pub fn upload_texture_atlas(&mut self, update: &AtlasUpdate) {
+        self.texture_atlas.set_texture_region(update.x, update.y, &update.image);
+        stats_texture(&mut self.stats, update.image.as_slice());
+    }
+
+    pub fn texture_create(&mut self, image: &Image, settings: &TextureSettings) {
+        let mut texture = TextureHandle::with_settings(self.gl.clone(), TextureUnit::Standalone, settings);
+        texture.set_texture(image);
+        stats_texture(&mut self.stats, image.as_slice());
+        self.textures.push(Some(texture));
+    }
+
+    pub fn texture_remove(&mut self, key: usize) {
+        self.textures[key] = None;
     }
 
     pub fn batch_create(&mut self, desc: &BatchSettings) {
@@ -92,6 +177,7 @@ impl OpenGLState {
             desc: *desc,
             sprites: Buffer::new(self.gl.clone(), BufferBindingTarget::ArrayBuffer),
             strings: Buffer::new(self.gl.clone(), BufferBindingTarget::ArrayBuffer),
+            sdf_strings: Buffer::new(self.gl.clone(), BufferBindingTarget::ArrayBuffer),
             matrix_transform,
             matrix_full,
         });
@@ -108,16 +194,27 @@ impl OpenGLState {
 
     pub fn batch_sprite_set(&mut self, index: usize, quads: &Vec<Sprite>) {
         self.batches[index].sprites.set(quads);
+        stats_buffer(&mut self.stats, quads);
     }
 
-    pub fn batch_string_set(&mut self, index: usize, quads: &Vec<Sprite>) {
-        self.batches[index].strings.set(quads);
+    pub fn batch_string_set(&mut self, index: usize, quads: &Vec<Sprite>, sdf_quads: &Vec<Sprite>) {
+        let batch = &mut self.batches[index];
+        batch.strings.set(quads);
+        batch.sdf_strings.set(sdf_quads);
+        stats_buffer(&mut self.stats, quads);
+        stats_buffer(&mut self.stats, sdf_quads);
     }
 
     pub fn batch_remove(&mut self, index: usize) {
         self.batches.swap_remove(index);
     }
 
+    /// Returns the work done since the last call. The atlas occupancy and frame time are left for
+    /// the caller to fill in.
+    pub fn stats_take(&mut self) -> RenderStats {
+        mem::take(&mut self.stats)
+    }
+
     /// Helper function to resize the window.
     fn resize(&mut self) {
         let new_logical_size = self.window.logical_size();
@@ -136,17 +233,97 @@ impl OpenGLState {
     pub fn draw(&mut self) {
         self.resize();
         self.gl.clear(ClearBit::ColorBuffer | ClearBit::DepthBuffer);
+        let textures = &self.textures;
+        stats_draw(&mut self.stats, self.batches.iter().map(|batch| batch.draw_info(textures)));
         for batch in &mut self.batches {
-            if batch.desc.visible {
-                self.shader.ortho(&batch.matrix_full);
-                if batch.sprites.len() > 0 {
-                    batch.sprites.draw();
-                }
-                if batch.strings.len() > 0 {
-                    batch.strings.draw();
+            let draw = batch.draw_info(&self.textures);
+            if !draw.visible {
+                continue;
+            }
+            self.shader.ortho(&batch.matrix_full);
+            if draw.draws_sprites() {
+                match batch.desc.texture {
+                    Some(token) => {
+                        if let Some(texture) = &self.textures[token.key()] {
+                            texture.bind();
+                            self.shader.texture(TextureUnit::Standalone);
+                            self.shader.uv_scale(token.uv_scale());
+                            batch.sprites.draw();
+                            self.shader.texture(TextureUnit::Atlas);
+                            self.shader.uv_scale(Vector2::new(1.0, 1.0));
+                        }
+                    }
+                    None => batch.sprites.draw(),
                 }
             }
+            if draw.draws_glyphs() {
+                batch.strings.draw();
+            }
+            if draw.draws_sdf_glyphs() {
+                self.shader.sdf(true);
+                batch.sdf_strings.draw();
+                self.shader.sdf(false);
+            }
         }
         self.window.swap_buffers();
     }
 }
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn stats_draws() {
+        let mut stats = RenderStats::default();
+        let draws = [
+            BatchDraw {
+                visible: true,
+                sprites: 3,
+                glyphs: 2,
+                sdf_glyphs: 1,
+                textured: true,
+            },
+            // Hidden batches aren't counted.
+            BatchDraw {
+                visible: false,
+                sprites: 5,
+                glyphs: 5,
+                sdf_glyphs: 5,
+                textured: true,
+            },
+            // Empty batches aren't counted, but their sprites are counted without a draw call when
+            // their texture was removed.
+            BatchDraw {
+                visible: true,
+                ..BatchDraw::default()
+            },
+            BatchDraw {
+                visible: true,
+                sprites: 4,
+                textured: false,
+                ..BatchDraw::default()
+            },
+        ];
+        stats_draw(&mut stats, draws.iter().copied());
+        assert_eq!(stats.batches, 2);
+        assert_eq!(stats.sprites, 7);
+        assert_eq!(stats.glyphs, 3);
+        assert_eq!(stats.draw_calls, 3);
+    }
+
+    #[test]
+    fn stats_uploads() {
+        let mut stats = RenderStats::default();
+        stats_buffer(&mut stats, &[Sprite::default(); 2]);
+        stats_buffer(&mut stats, &[]);
+        stats_texture(&mut stats, &[RGBA8::new_raw(0, 0, 0, 0); 6]);
+        assert_eq!(stats.buffer_bytes, 2 * mem::size_of::<Sprite>());
+        assert_eq!(stats.texture_bytes, 24);
+    }
+}
@@ -3,6 +3,7 @@ use crate::render::gl::raw::{
     TextureMagFilterValue, TextureMinFilterValue, TextureParameterTarget, TextureUnit, TextureWrapValue,
 };
 use crate::texture::*;
+use crate::types::*;
 
 static DEFAULT: [u8; 4] = [255u8, 255u8, 255u8, 255u8];
 
@@ -10,31 +11,73 @@ pub struct TextureHandle {
     gl: OpenGL,
     id: resource::Texture,
     unit: TextureUnit,
+    settings: TextureSettings,
+    width: u32,
+    height: u32,
 }
 
 impl TextureHandle {
     pub fn new(gl: OpenGL, texture_unit: TextureUnit) -> TextureHandle {
+        TextureHandle::with_settings(gl, texture_unit, &TextureSettings::default())
+    }
+
+    pub fn with_settings(gl: OpenGL, texture_unit: TextureUnit, settings: &TextureSettings) -> TextureHandle {
         let id = gl.create_texture();
         let unit = texture_unit;
-        let texture = TextureHandle {
+        let mut texture = TextureHandle {
             gl,
             id,
             unit,
+            settings: *settings,
+            width: 0,
+            height: 0,
         };
         texture.set_raw(1, 1, &DEFAULT);
         texture
     }
 
-    pub fn set_texture(&self, texture: &Image) {
+    /// Binds the texture to its texture unit.
+    pub fn bind(&self) {
+        self.gl.active_texture(self.unit);
+        self.gl.bind_texture(TextureBindingTarget::Texture2D, Some(self.id));
+    }
+
+    pub fn set_texture(&mut self, texture: &Image) {
         let width = texture.width() as i32;
         let height = texture.height() as i32;
         let slice = texture.as_slice();
         self.set_raw(width, height, slice);
     }
 
-    fn set_raw<T: Sized>(&self, width: i32, height: i32, buffer: &[T]) {
-        self.gl.active_texture(self.unit);
-        self.gl.bind_texture(TextureBindingTarget::Texture2D, Some(self.id));
+    /// Overwrites a region of the texture. If the region starts at the origin and covers the whole
+    /// texture, the texture is reallocated to the size of the given image instead.
+    pub fn set_texture_region(&mut self, offset_x: u32, offset_y: u32, texture: &Image) {
+        if offset_x == 0 && offset_y == 0 && texture.width() >= self.width && texture.height() >= self.height
+        {
+            self.set_texture(texture);
+            return;
+        }
+        self.bind();
+        self.gl.tex_sub_image_2d(
+            TextureLoadTarget::Texture2D,
+            0,
+            offset_x as i32,
+            offset_y as i32,
+            texture.width() as i32,
+            texture.height() as i32,
+            PixelFormat::RGBA,
+            PixelType::UnsignedByte,
+            texture.as_slice(),
+        );
+        if self.settings.mipmaps {
+            self.gl.generate_mipmap(TextureBindingTarget::Texture2D);
+        }
+    }
+
+    fn set_raw<T: Sized>(&mut self, width: i32, height: i32, buffer: &[T]) {
+        self.width = width as u32;
+        self.height = height as u32;
+        self.bind();
         self.gl.tex_image_2d(
             TextureLoadTarget::Texture2D,
             0,
@@ -46,10 +89,28 @@ impl TextureHandle {
             PixelType::UnsignedByte,
             buffer,
         );
-        self.gl.tex_parameter_wrap_s(TextureParameterTarget::Texture2D, TextureWrapValue::ClampToEdge);
-        self.gl.tex_parameter_wrap_t(TextureParameterTarget::Texture2D, TextureWrapValue::ClampToEdge);
-        self.gl.tex_parameter_min_filter(TextureParameterTarget::Texture2D, TextureMinFilterValue::Nearest);
-        self.gl.tex_parameter_mag_filter(TextureParameterTarget::Texture2D, TextureMagFilterValue::Nearest);
+        let wrap = match self.settings.wrap {
+            TextureWrap::ClampToEdge => TextureWrapValue::ClampToEdge,
+            TextureWrap::Repeat => TextureWrapValue::Repeat,
+            TextureWrap::MirroredRepeat => TextureWrapValue::MirroredRepeat,
+        };
+        let min_filter = match (self.settings.min_filter, self.settings.mipmaps) {
+            (TextureFilter::Nearest, false) => TextureMinFilterValue::Nearest,
+            (TextureFilter::Linear, false) => TextureMinFilterValue::Linear,
+            (TextureFilter::Nearest, true) => TextureMinFilterValue::NearestMipmapLinear,
+            (TextureFilter::Linear, true) => TextureMinFilterValue::LinearMipmapLinear,
+        };
+        let mag_filter = match self.settings.mag_filter {
+            TextureFilter::Nearest => TextureMagFilterValue::Nearest,
+            TextureFilter::Linear => TextureMagFilterValue::Linear,
+        };
+        self.gl.tex_parameter_wrap_s(TextureParameterTarget::Texture2D, wrap);
+        self.gl.tex_parameter_wrap_t(TextureParameterTarget::Texture2D, wrap);
+        self.gl.tex_parameter_min_filter(TextureParameterTarget::Texture2D, min_filter);
+        self.gl.tex_parameter_mag_filter(TextureParameterTarget::Texture2D, mag_filter);
+        if self.settings.mipmaps {
+            self.gl.generate_mipmap(TextureBindingTarget::Texture2D);
+        }
     }
 }
 
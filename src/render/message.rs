@@ -5,7 +5,8 @@ use crate::RGBA8;
 pub struct RenderState {
     pub batches: Vec<BatchState>,
     pub batch_changes: Vec<BatchMessage>,
-    pub atlas: Option<Image>,
+    pub texture_changes: Vec<TextureMessage>,
+    pub atlas: Option<AtlasUpdate>,
     pub window: WindowState,
 }
 
@@ -14,6 +15,7 @@ impl Default for RenderState {
         RenderState {
             batches: Vec::new(),
             batch_changes: Vec::new(),
+            texture_changes: Vec::new(),
             atlas: None,
             window: WindowState::default(),
         }
@@ -23,6 +25,7 @@ impl Default for RenderState {
 pub struct BatchState {
     pub sprites: Vec<Sprite>,
     pub strings: Vec<Sprite>,
+    pub sdf_strings: Vec<Sprite>,
     pub dirty_sprites: bool,
     pub dirty_strings: bool,
 }
@@ -32,6 +35,7 @@ impl Default for BatchState {
         BatchState {
             sprites: Vec::new(),
             strings: Vec::new(),
+            sdf_strings: Vec::new(),
             dirty_sprites: false,
             dirty_strings: false,
         }
@@ -52,6 +56,16 @@ pub enum BatchMessage {
     },
 }
 
+pub enum TextureMessage {
+    Create {
+        image: Image,
+        settings: TextureSettings,
+    },
+    Remove {
+        key: usize,
+    },
+}
+
 pub struct WindowState {
     pub title: Option<String>,
     pub clear_color: Option<RGBA8>,
@@ -1,13 +1,19 @@
+use crate::profile::{self, Frame};
 use crate::render::gl::OpenGLState;
 use crate::render::message::*;
-use crate::time::*;
-use crate::types::WindowSettings;
-use crate::utility::swap_spsc;
+use crate::types::{RenderStats, WindowSettings};
+use crate::utility::{bounded_spsc, swap_spsc};
+
+/// What the render thread sends back to the game thread after each frame it draws.
+pub struct RenderFrame {
+    pub profile: Frame,
+    pub stats: RenderStats,
+}
 
 pub struct RenderServer {
     render_consumer: swap_spsc::Consumer<RenderState>,
+    frame_producer: bounded_spsc::Producer<RenderFrame>,
     state: OpenGLState,
-    timer_render: Timer,
 }
 
 impl RenderServer {
@@ -15,26 +21,40 @@ impl RenderServer {
         desc: &WindowSettings,
         event_loop: &glutin::event_loop::EventLoop<()>,
         render_consumer: swap_spsc::Consumer<RenderState>,
+        frame_producer: bounded_spsc::Producer<RenderFrame>,
     ) -> RenderServer {
         RenderServer {
             render_consumer,
+            frame_producer,
             state: OpenGLState::new(desc, event_loop),
-            timer_render: Timer::new("[R] Frame"),
         }
     }
 
     pub fn tick(&mut self) {
         if self.render_consumer.try_next() {
-            self.timer_render.start();
-            self.update();
-            self.state.draw();
-            self.timer_render.stop();
+            {
+                let _scope = profile::scope("update");
+                self.update();
+            }
+            {
+                let _scope = profile::scope("draw");
+                self.state.draw();
+            }
+            let profile = profile::frame_end();
+            let mut stats = self.state.stats_take();
+            stats.frame_time = profile.end - profile.start;
+            // Frames are dropped if the game thread isn't reading them.
+            self.frame_producer.try_push(RenderFrame {
+                profile,
+                stats,
+            });
         }
     }
 
     fn update(&mut self) {
         let messages = self.render_consumer.get();
         if let Some(atlas) = messages.atlas.take() {
+            let _scope = profile::scope("atlas upload");
             self.state.upload_texture_atlas(&atlas);
         }
         if let Some(title) = messages.window.title.take() {
@@ -49,6 +69,20 @@ impl RenderServer {
         if let Some(vsync) = messages.window.vsync.take() {
             self.state.window_vsync(vsync);
         }
+        for message in messages.texture_changes.drain(..) {
+            match message {
+                TextureMessage::Create {
+                    image,
+                    settings,
+                } => {
+                    let _scope = profile::scope("texture upload");
+                    self.state.texture_create(&image, &settings);
+                }
+                TextureMessage::Remove {
+                    key,
+                } => self.state.texture_remove(key),
+            }
+        }
         for message in messages.batch_changes.drain(..) {
             match message {
                 BatchMessage::Create {
@@ -66,11 +100,13 @@ impl RenderServer {
         let mut index = 0;
         for batch in &mut messages.batches {
             if batch.dirty_sprites {
+                let _scope = profile::scope("sprite buffer upload");
                 self.state.batch_sprite_set(index, &batch.sprites);
                 batch.dirty_sprites = false;
             }
             if batch.dirty_strings {
-                self.state.batch_string_set(index, &batch.strings);
+                let _scope = profile::scope("text buffer upload");
+                self.state.batch_string_set(index, &batch.strings, &batch.sdf_strings);
                 batch.dirty_strings = false;
             }
             index += 1;
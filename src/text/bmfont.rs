@@ -0,0 +1,477 @@
+use crate::text::layout::{Glyph, Item, LineMetrics};
+use crate::text::shaping::{self, ShapedChar};
+use crate::texture::PIXEL_SIZE;
+use crate::*;
+use cgmath::*;
+use hashbrown::HashMap;
+
+/// A character in a bitmap font, in pixels of its page.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BitmapChar {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Offset of the character's image from the pen, measured right and down from the top of the
+    /// line.
+    pub xoffset: i32,
+    pub yoffset: i32,
+    pub xadvance: i32,
+    pub page: usize,
+}
+
+/// A font drawn from hand made images, loaded from an AngelCode BMFont descriptor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BitmapFont {
+    /// Size the font was drawn at, in pixels.
+    size: u32,
+    line_height: u32,
+    /// Distance from the top of a line to the baseline.
+    base: u32,
+    /// File names of the page images, relative to the descriptor.
+    pages: Vec<String>,
+    chars: HashMap<char, BitmapChar>,
+    kerning: HashMap<(char, char), i32>,
+    /// Atlas UVs of each page, once they've been added to the atlas.
+    page_uvs: Vec<Vector4<u16>>,
+}
+
+impl BitmapFont {
+    /// Parses a BMFont descriptor in either the text or binary format.
+    pub fn parse(bytes: &[u8]) -> Result<BitmapFont, &'static str> {
+        let font = if bytes.starts_with(b"BMF") {
+            Self::parse_binary(bytes)?
+        } else {
+            match std::str::from_utf8(bytes) {
+                Ok(text) => Self::parse_text(text)?,
+                Err(_) => Err("Bitmap font descriptor isn't valid text.")?,
+            }
+        };
+        if font.line_height == 0 || font.pages.is_empty() {
+            Err("Bitmap font descriptor is missing its common or page blocks.")?
+        }
+        if font.chars.values().any(|c| c.page >= font.pages.len()) {
+            Err("Bitmap font character refers to a missing page.")?
+        }
+        Ok(font)
+    }
+
+    fn empty() -> BitmapFont {
+        BitmapFont {
+            size: 0,
+            line_height: 0,
+            base: 0,
+            pages: Vec::new(),
+            chars: HashMap::new(),
+            kerning: HashMap::new(),
+            page_uvs: Vec::new(),
+        }
+    }
+
+    fn parse_text(text: &str) -> Result<BitmapFont, &'static str> {
+        let mut font = BitmapFont::empty();
+        for line in text.lines() {
+            let mut tokens = tokenize(line);
+            let tag = match tokens.next() {
+                Some(tag) => tag,
+                None => continue,
+            };
+            let mut values = HashMap::new();
+            for token in tokens {
+                if let Some(split) = token.find('=') {
+                    values.insert(&token[..split], token[split + 1..].trim_matches('"'));
+                }
+            }
+            let int = |key: &str| -> Result<i32, &'static str> {
+                match values.get(key).map(|value| value.parse::<i32>()) {
+                    Some(Ok(value)) => Ok(value),
+                    Some(Err(_)) => Err("Bitmap font descriptor has an invalid number."),
+                    None => Ok(0),
+                }
+            };
+            match tag {
+                "info" => font.size = int("size")?.unsigned_abs(),
+                "common" => {
+                    font.line_height = int("lineHeight")?.max(0) as u32;
+                    font.base = int("base")?.max(0) as u32;
+                }
+                "page" => {
+                    let id = int("id")?.max(0) as usize;
+                    let file = values.get("file").copied().unwrap_or("");
+                    if font.pages.len() <= id {
+                        font.pages.resize(id + 1, String::new());
+                    }
+                    font.pages[id] = file.to_string();
+                }
+                "char" => {
+                    let id = char_from(int("id")?)?;
+                    font.chars.insert(
+                        id,
+                        BitmapChar {
+                            x: int("x")?.max(0) as u32,
+                            y: int("y")?.max(0) as u32,
+                            width: int("width")?.max(0) as u32,
+                            height: int("height")?.max(0) as u32,
+                            xoffset: int("xoffset")?,
+                            yoffset: int("yoffset")?,
+                            xadvance: int("xadvance")?,
+                            page: int("page")?.max(0) as usize,
+                        },
+                    );
+                }
+                "kerning" => {
+                    let pair = (char_from(int("first")?)?, char_from(int("second")?)?);
+                    font.kerning.insert(pair, int("amount")?);
+                }
+                _ => {}
+            }
+        }
+        Ok(font)
+    }
+
+    fn parse_binary(bytes: &[u8]) -> Result<BitmapFont, &'static str> {
+        if bytes.len() < 4 || bytes[3] != 3 {
+            Err("Only version 3 of the binary bitmap font format is supported.")?
+        }
+        let u16_at = |block: &[u8], at: usize| u16::from_le_bytes([block[at], block[at + 1]]);
+        let u32_at = |block: &[u8], at: usize| {
+            u32::from_le_bytes([block[at], block[at + 1], block[at + 2], block[at + 3]])
+        };
+
+        let mut font = BitmapFont::empty();
+        let mut at = 4;
+        while at < bytes.len() {
+            if at + 5 > bytes.len() {
+                Err("Bitmap font descriptor is truncated.")?
+            }
+            let kind = bytes[at];
+            let size = u32_at(bytes, at + 1) as usize;
+            at += 5;
+            if at + size > bytes.len() {
+                Err("Bitmap font descriptor is truncated.")?
+            }
+            let block = &bytes[at..at + size];
+            at += size;
+            match kind {
+                1 if size >= 2 => font.size = (u16_at(block, 0) as i16).unsigned_abs() as u32,
+                2 if size >= 4 => {
+                    font.line_height = u16_at(block, 0) as u32;
+                    font.base = u16_at(block, 2) as u32;
+                }
+                3 => {
+                    font.pages = block
+                        .split(|&byte| byte == 0)
+                        .filter(|name| !name.is_empty())
+                        .map(|name| String::from_utf8_lossy(name).into_owned())
+                        .collect();
+                }
+                4 => {
+                    for record in block.chunks_exact(20) {
+                        let id = char_from(u32_at(record, 0) as i32)?;
+                        font.chars.insert(
+                            id,
+                            BitmapChar {
+                                x: u16_at(record, 4) as u32,
+                                y: u16_at(record, 6) as u32,
+                                width: u16_at(record, 8) as u32,
+                                height: u16_at(record, 10) as u32,
+                                xoffset: u16_at(record, 12) as i16 as i32,
+                                yoffset: u16_at(record, 14) as i16 as i32,
+                                xadvance: u16_at(record, 16) as i16 as i32,
+                                page: record[18] as usize,
+                            },
+                        );
+                    }
+                }
+                5 => {
+                    for record in block.chunks_exact(10) {
+                        let pair =
+                            (char_from(u32_at(record, 0) as i32)?, char_from(u32_at(record, 4) as i32)?);
+                        font.kerning.insert(pair, u16_at(record, 8) as i16 as i32);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(font)
+    }
+
+    /// File names of the page images, relative to the descriptor, in page order.
+    pub fn pages(&self) -> &[String] {
+        &self.pages
+    }
+
+    /// Sets the atlas UVs of each page, in page order.
+    pub fn set_page_uvs(&mut self, uvs: Vec<Vector4<u16>>) {
+        self.page_uvs = uvs;
+    }
+
+    /// Returns how many times larger than its drawn size the font is shown for the given text
+    /// scale. Bitmap fonts are only scaled by whole multiples to keep their pixels crisp.
+    pub fn factor(&self, scale: u32) -> f32 {
+        if self.size == 0 {
+            return 1.0;
+        }
+        (scale as f32 / self.size as f32).round().max(1.0)
+    }
+
+    // Characters the font is missing are drawn as a question mark if it has one.
+    fn glyph(&self, c: char) -> Option<&BitmapChar> {
+        self.chars.get(&c).or_else(|| self.chars.get(&'?'))
+    }
+
+    fn uv(&self, glyph: &BitmapChar) -> Option<Vector4<u16>> {
+        if glyph.width == 0 || glyph.height == 0 {
+            return None;
+        }
+        let page = self.page_uvs.get(glyph.page)?;
+        let left = page.x as u32 + glyph.x * PIXEL_SIZE;
+        let top = page.z as u32 + glyph.y * PIXEL_SIZE;
+        Some(Vector4::new(
+            left as u16,
+            (left + glyph.width * PIXEL_SIZE) as u16,
+            top as u16,
+            (top + glyph.height * PIXEL_SIZE) as u16,
+        ))
+    }
+}
+
+// Splits a line on spaces, keeping quoted values together.
+fn tokenize(line: &str) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    line.split(move |c: char| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == ' ' && !quoted
+    })
+    .filter(|token| !token.is_empty())
+}
+
+fn char_from(id: i32) -> Result<char, &'static str> {
+    match std::char::from_u32(id as u32) {
+        Some(c) => Ok(c),
+        None => Err("Bitmap font descriptor has an invalid character id."),
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Shaping
+// ////////////////////////////////////////////////////////////////////////////
+
+impl BitmapFont {
+    /// Returns if the font has an image for the character.
+    pub fn has(&self, c: char) -> bool {
+        self.chars.contains_key(&c)
+    }
+
+    /// Returns the vertical metrics of a line drawn at the given text scale.
+    pub fn line_metrics(&self, scale: u32) -> LineMetrics {
+        let factor = self.factor(scale);
+        LineMetrics {
+            ascent: self.base as f32 * factor,
+            descent: (self.base as f32 - self.line_height as f32) * factor,
+            gap: 0.0,
+        }
+    }
+
+    /// Shapes characters drawn with the font into items, in logical order. Bitmap fonts have no
+    /// shaping tables, so characters are substituted with their composed and contextual forms,
+    /// mirrored in right-to-left text, and kerned against their neighbors.
+    pub fn shape(
+        &self,
+        chars: &[ShapedChar],
+        scale: u32,
+        color: RGBA8,
+        (level, paragraph): (u8, u8),
+        items: &mut Vec<Item>,
+    ) {
+        let factor = self.factor(scale);
+        let metrics = self.line_metrics(scale);
+        let rtl = level % 2 == 1;
+        let mut previous: Option<char> = None;
+        for ch in shaping::substitute(chars) {
+            let c = if rtl {
+                shaping::mirror(ch.c)
+            } else {
+                ch.c
+            };
+            let glyph = self.glyph(c);
+            // Kerning moves the character on the right of the pair, so it's added to the advance of
+            // the one on the left. In right-to-left text, that's the later character.
+            let kerning = match previous {
+                Some(last) if rtl => self.kerning.get(&(c, last)),
+                Some(last) => self.kerning.get(&(last, c)),
+                None => None,
+            };
+            let kerning = kerning.copied().unwrap_or(0) as f32 * factor;
+            if !rtl {
+                if let Some(last) = items.last_mut() {
+                    last.advance += kerning;
+                }
+            }
+            let (drawn, offset) = match glyph.and_then(|glyph| self.uv(glyph).map(|uv| (glyph, uv))) {
+                Some((glyph, uv)) => {
+                    let size = Vector2::new(glyph.width as f32, glyph.height as f32) * factor;
+                    // The image hangs down from the top of the font's line.
+                    let top = (self.base as i32 - glyph.yoffset) as f32 * factor;
+                    let offset = Vector2::new(glyph.xoffset as f32 * factor, top - size.y);
+                    (
+                        Glyph::Bitmap {
+                            uv,
+                            size,
+                        },
+                        offset,
+                    )
+                }
+                None => (Glyph::None, Vector2::zero()),
+            };
+            let advance = glyph.map_or(0, |glyph| glyph.xadvance) as f32 * factor;
+            items.push(Item {
+                glyph: drawn,
+                color,
+                cluster: ch.source,
+                advance: if rtl {
+                    advance + kerning
+                } else {
+                    advance
+                },
+                offset,
+                level,
+                paragraph,
+                whitespace: c.is_whitespace(),
+                newline: false,
+                metrics,
+            });
+            previous = Some(c);
+        }
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+    use crate::colors::*;
+    use crate::text::layout;
+
+    const TEXT: &str = "info face=\"Pixel Font\" size=8 bold=0\n\
+                        common lineHeight=10 base=8 scaleW=64 scaleH=64 pages=1\n\
+                        page id=0 file=\"pixel font.png\"\n\
+                        chars count=3\n\
+                        char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=4 page=0\n\
+                        char id=65 x=0 y=0 width=5 height=8 xoffset=0 yoffset=0 xadvance=6 page=0\n\
+                        char id=86 x=5 y=0 width=5 height=8 xoffset=0 yoffset=0 xadvance=6 page=0\n\
+                        kernings count=1\n\
+                        kerning first=65 second=86 amount=-1\n";
+
+    fn binary() -> Vec<u8> {
+        let mut bytes = b"BMF\x03".to_vec();
+        let mut block = |kind: u8, data: Vec<u8>| {
+            bytes.push(kind);
+            bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            bytes.extend(data);
+        };
+        let mut info = 8i16.to_le_bytes().to_vec();
+        info.extend_from_slice(&[0; 12]);
+        info.extend_from_slice(b"Pixel Font\0");
+        block(1, info);
+        let mut common = Vec::new();
+        for value in &[10u16, 8, 64, 64, 1] {
+            common.extend_from_slice(&value.to_le_bytes());
+        }
+        common.extend_from_slice(&[0; 5]);
+        block(2, common);
+        block(3, b"pixel font.png\0".to_vec());
+        let mut chars = Vec::new();
+        for &(id, x, advance) in &[(32u32, 0u16, 4i16), (65, 0, 6), (86, 5, 6)] {
+            let size = if id == 32 {
+                (0u16, 0u16)
+            } else {
+                (5, 8)
+            };
+            chars.extend_from_slice(&id.to_le_bytes());
+            for value in &[x, 0, size.0, size.1, 0, 0] {
+                chars.extend_from_slice(&value.to_le_bytes());
+            }
+            chars.extend_from_slice(&advance.to_le_bytes());
+            chars.extend_from_slice(&[0, 15]);
+        }
+        block(4, chars);
+        let mut kerning = 65u32.to_le_bytes().to_vec();
+        kerning.extend_from_slice(&86u32.to_le_bytes());
+        kerning.extend_from_slice(&(-1i16).to_le_bytes());
+        block(5, kerning);
+        bytes
+    }
+
+    #[test]
+    fn parse_formats() {
+        let text = BitmapFont::parse(TEXT.as_bytes()).unwrap();
+        assert_eq!(text.pages(), &["pixel font.png".to_string()]);
+        assert_eq!((text.size, text.line_height, text.base), (8, 10, 8));
+        assert_eq!(text.chars[&'V'].x, 5);
+        assert_eq!(text.kerning[&('A', 'V')], -1);
+        assert_eq!(BitmapFont::parse(&binary()).unwrap(), text);
+
+        assert!(BitmapFont::parse(b"info size=8\n").is_err());
+        assert!(BitmapFont::parse(&binary()[..20]).is_err());
+    }
+
+    #[test]
+    fn layout_kerning_and_wrapping() {
+        let mut font = BitmapFont::parse(TEXT.as_bytes()).unwrap();
+        font.set_page_uvs(vec![Vector4::new(0, 64 * PIXEL_SIZE as u16, 0, 64 * PIXEL_SIZE as u16)]);
+        let chars: Vec<ShapedChar> = "AV A"
+            .chars()
+            .enumerate()
+            .map(|(source, c)| ShapedChar {
+                c,
+                source,
+            })
+            .collect();
+        let mut items = Vec::new();
+        font.shape(&chars, 16, BLACK, (0, 0), &mut items);
+        let mut desc = Text::default();
+        desc.pos = Vector3::new(0.0, 100.0, 0.0);
+        desc.horizontal_align = HorizontalAlign::Left;
+
+        // Scale 16 draws the 8 pixel font at twice its size, with 4 pixels below the baseline.
+        let laid = layout::layout(&desc, &items, chars.len(), font.line_metrics(16));
+        let placed = &laid.placed;
+        assert_eq!(placed[1].origin, Vector2::new(10.0, 104.0));
+        assert_eq!(placed[1].item.offset, Vector2::new(0.0, 0.0));
+        assert_eq!(
+            placed[1].item.glyph,
+            Glyph::Bitmap {
+                uv: Vector4::new(5 * PIXEL_SIZE as u16, 10 * PIXEL_SIZE as u16, 0, 8 * PIXEL_SIZE as u16),
+                size: Vector2::new(10.0, 16.0),
+            }
+        );
+        assert_eq!(laid.carets[4].pos, Vector2::new(42.0, 104.0));
+        assert_eq!(laid.lines.len(), 1);
+
+        // Without a max width, the line is aligned around the position.
+        desc.horizontal_align = HorizontalAlign::Center;
+        let laid = layout::layout(&desc, &items, chars.len(), font.line_metrics(16));
+        assert_eq!(laid.placed[0].origin, Vector2::new(-21.0, 104.0));
+        assert_eq!(laid.carets[4].pos, Vector2::new(21.0, 104.0));
+        desc.horizontal_align = HorizontalAlign::Right;
+        let laid = layout::layout(&desc, &items, chars.len(), font.line_metrics(16));
+        assert_eq!(laid.placed[0].origin, Vector2::new(-42.0, 104.0));
+        assert_eq!(laid.carets[4].pos, Vector2::new(0.0, 104.0));
+        desc.horizontal_align = HorizontalAlign::Left;
+
+        // The last word wraps onto the next line.
+        desc.max_width = Some(30.0);
+        let laid = layout::layout(&desc, &items, chars.len(), font.line_metrics(16));
+        assert_eq!(laid.lines.len(), 2);
+        assert_eq!(laid.placed[0].origin, Vector2::new(0.0, 124.0));
+        assert_eq!(laid.placed[3].origin, Vector2::new(0.0, 104.0));
+        assert_eq!(laid.placed[3].line, 1);
+    }
+}
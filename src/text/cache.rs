@@ -1,22 +1,120 @@
+use crate::colors::*;
+use crate::text::bmfont::BitmapFont;
+use crate::text::effect;
+use crate::text::layout::{self, Glyph, Item, Layout, LineMetrics};
+use crate::text::sdf::{self, SDF_SIZE, SDF_SPREAD};
+use crate::text::shaping::{self, ShapedChar};
+use crate::text::shelf::{ShelfAllocator, Slot};
 use crate::texture::*;
 use crate::*;
 use cgmath::*;
-use fontdue::layout::{GlyphRasterConfig, HorizontalAlign, Layout, LayoutSettings, TextStyle, VerticalAlign};
 use fontdue::{Font, FontSettings};
 use hashbrown::HashMap;
-use std::fs::File;
-use std::io::{BufReader, Read};
-use std::path::Path;
+use rustybuzz::{Direction, Face, UnicodeBuffer};
+use std::borrow::Cow;
+use unicode_normalization::char::canonical_combining_class;
+
+static DEFAULT_FONT: &[u8] = include_bytes!("fonts/Roboto-Regular.ttf");
+
+// Size in pixels of the square region of the atlas glyphs are allocated from.
+const GLYPH_REGION: u32 = 1024;
+
+// Depth between the layers of text effects, placing each layer behind the one above it.
+const EFFECT_DEPTH: f32 = 0.00001;
+
+// The style of the string or a span, resolved against the text it belongs to.
+#[derive(Copy, Clone)]
+struct Style {
+    scale: u32,
+    color: RGBA8,
+    font: usize,
+}
+
+// A glyph of a font rasterized at a size in pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    font: usize,
+    glyph: u16,
+    px: u32,
+}
+
+// How a cached glyph was rasterized.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Effect {
+    Plain,
+    Sdf,
+    Outline(u32),
+    Glow(u32),
+    // Outline of a distance field glyph, in quarter pixels at SDF_SIZE.
+    SdfOutline(u32),
+}
+
+impl Effect {
+    fn is_sdf(self) -> bool {
+        matches!(self, Effect::Sdf | Effect::SdfOutline(_))
+    }
+}
+
+// A font drawn from its outlines.
+struct VectorFont {
+    font: Font,
+    // The shaper's view of the font, parsed once on load. It borrows from bytes, so it's declared
+    // first to be dropped before them.
+    face: Face<'static>,
+    // The file the font was parsed from. It's never changed once loaded.
+    bytes: Cow<'static, [u8]>,
+    // If the font is drawn with distance fields.
+    sdf: bool,
+}
+
+// A loaded font, by its index.
+enum FontSlot {
+    // Boxed, since the parsed face makes it far larger than the others.
+    Vector(Box<VectorFont>),
+    // A bitmap font, whose pages are already in the atlas.
+    Bitmap(BitmapFont),
+    // A font that was unloaded. Text still using it is drawn with the default font.
+    Removed,
+}
+
+// A laid out glyph waiting to be turned into sprites.
+struct Placement {
+    key: GlyphKey,
+    // The glyph's origin on the baseline.
+    origin: Vector2<f32>,
+    color: RGBA8,
+}
 
 #[derive(Debug, Copy, Clone)]
 struct CharCacheValue {
     uv: Vector4<u16>,
     size: Vector2<f32>,
+    // Offset of the bottom left of the bitmap from the glyph's origin on the baseline.
+    offset: Vector2<f32>,
+    // Where the glyph and its padding live in the glyph region. Glyphs that didn't fit the region
+    // are added to the atlas permanently, and have no slot.
+    slot: Option<Slot>,
+    // Value of the use counter the last time the glyph was drawn.
+    last_used: u64,
 }
 
 pub struct TextCache {
-    cache: HashMap<GlyphRasterConfig, CharCacheValue>,
-    fonts: Vec<Font>,
+    cache: HashMap<(GlyphKey, Effect), CharCacheValue>,
+    fonts: Vec<FontSlot>,
+    // Fonts searched, in order, for characters missing from the font at the same index.
+    fallbacks: Vec<Vec<usize>>,
+    // Position of the glyph region in the atlas and its allocator, reserved on first use.
+    region: Option<((u32, u32), ShelfAllocator)>,
+    // Number of owners drawing each slot. Slots in use are never evicted.
+    refs: HashMap<Slot, u32>,
+    // Slots each owner is drawing, released when the owner's text is replaced.
+    owners: HashMap<usize, Vec<Slot>>,
+    // Slots pinned since the last settle, waiting to be assigned to an owner.
+    pending: Vec<Slot>,
+    // Slots dropped from the cache while still in use, freed once released.
+    orphans: Vec<Slot>,
+    // Incremented each time text is rasterized, to find the least recently used glyphs.
+    uses: u64,
     dirty: bool,
 }
 
@@ -25,73 +123,703 @@ impl TextCache {
         let mut manager = TextCache {
             cache: HashMap::new(),
             fonts: Vec::new(),
+            fallbacks: Vec::new(),
+            region: None,
+            refs: HashMap::new(),
+            owners: HashMap::new(),
+            pending: Vec::new(),
+            orphans: Vec::new(),
+            uses: 0,
             dirty: true,
         };
-        manager.add_font_bytes(include_bytes!("fonts/Roboto-Regular.ttf") as &[u8]);
+        let font = Self::parse_font(Cow::Borrowed(DEFAULT_FONT), false);
+        manager.fonts.push(font.expect("Unable to parse the default font."));
+        manager.fallbacks.push(Vec::new());
         manager
     }
 
-    pub fn add_font_bytes(&mut self, bytes: &[u8]) -> usize {
-        let index = self.fonts.len();
+    fn parse_font(bytes: Cow<'static, [u8]>, sdf: bool) -> Result<FontSlot, &'static str> {
         let settings = FontSettings {
             scale: 20.0,
             ..FontSettings::default()
         };
-        self.fonts.push(Font::from_bytes(bytes, settings).expect("Unable to parse font."));
+        // The shaper parses the font separately, so both have to accept it. The contents of the Cow
+        // are either static or on the heap, so they stay put when it's moved into the slot, and
+        // outlive the face borrowing them.
+        let data: &'static [u8] = unsafe { &*(&*bytes as *const [u8]) };
+        let face = match Face::from_slice(data, 0) {
+            Some(face) => face,
+            None => Err("Unable to parse font.")?,
+        };
+        match Font::from_bytes(&*bytes, settings) {
+            Ok(font) => Ok(FontSlot::Vector(Box::new(VectorFont {
+                font,
+                face,
+                bytes,
+                sdf,
+            }))),
+            Err(_) => Err("Unable to parse font."),
+        }
+    }
+
+    pub fn add_font_bytes(&mut self, bytes: &[u8], sdf: bool) -> Result<usize, &'static str> {
+        let index = self.fonts.len();
+        self.fonts.push(Self::parse_font(Cow::Owned(bytes.to_vec()), sdf)?);
+        self.fallbacks.push(Vec::new());
         info!("Loaded font from bytes.");
+        Ok(index)
+    }
+
+    /// Adds a bitmap font whose pages have already been added to the atlas.
+    pub fn add_bitmap_font(&mut self, font: BitmapFont) -> usize {
+        let index = self.fonts.len();
+        self.fonts.push(FontSlot::Bitmap(font));
+        self.fallbacks.push(Vec::new());
+        info!("Loaded bitmap font.");
         index
     }
 
-    pub fn add_font_path(&mut self, path: &str) -> usize {
-        let file = File::open(Path::new(path)).expect("Unable to read path.");
-        let mut file = BufReader::new(file);
-        let mut bytes = Vec::new();
-        file.read_to_end(&mut bytes).expect("Unable to read bytes.");
-        self.add_font_bytes(&bytes)
-    }
-
-    pub fn rasterize(&mut self, atlas: &mut TextureAtlas, desc: &Text, quads: &mut Vec<Sprite>) {
-        let mut layout = Layout::new();
-        let font_index = desc.font.key();
-        let font = &self.fonts[font_index];
-        let settings = LayoutSettings {
-            x: desc.pos.x,
-            y: desc.pos.y,
-            max_width: desc.max_width,
-            max_height: Some(500.0),
-            horizontal_align: HorizontalAlign::Center,
-            vertical_align: VerticalAlign::Middle,
-            ..LayoutSettings::default()
+    /// Replaces the font at the given index, dropping its cached glyphs.
+    pub fn replace_font_bytes(&mut self, index: usize, bytes: &[u8], sdf: bool) -> Result<(), &'static str> {
+        self.fonts[index] = Self::parse_font(Cow::Owned(bytes.to_vec()), sdf)?;
+        self.drop_glyphs(index);
+        info!("Replaced font {}.", index);
+        Ok(())
+    }
+
+    /// Unloads the font at the given index. The layout addresses fonts by index, so the slot is
+    /// kept, and text still using it is drawn with whichever font is the default at the time.
+    pub fn remove_font(&mut self, index: usize) {
+        self.fonts[index] = FontSlot::Removed;
+        self.fallbacks[index].clear();
+        self.drop_glyphs(index);
+        info!("Removed font {}.", index);
+    }
+
+    // Drops the cached glyphs of the font at the given index.
+    fn drop_glyphs(&mut self, index: usize) {
+        let stale: Vec<Option<Slot>> = self
+            .cache
+            .iter()
+            .filter(|((key, _), _)| key.font == index)
+            .map(|(_, value)| value.slot)
+            .collect();
+        self.cache.retain(|(key, _), _| key.font != index);
+        for slot in stale.into_iter().flatten() {
+            self.free_slot(slot);
+        }
+    }
+
+    /// Sets the fonts searched for characters missing from the font at the given index.
+    pub fn set_fallbacks(&mut self, index: usize, fallbacks: &[usize]) {
+        self.fallbacks[index] = fallbacks.iter().copied().filter(|&font| font != index).collect();
+    }
+
+    // Gets the index of the font drawing text set in the given font, which is the default font if
+    // the font was removed.
+    fn resolve(&self, font: usize) -> usize {
+        match self.fonts[font] {
+            FontSlot::Removed => 0,
+            _ => font,
+        }
+    }
+
+    // Picks the font to draw the character with: the given font if it has the character, otherwise
+    // the first font in its fallback chain that does.
+    fn pick(&self, font: usize, c: char) -> usize {
+        let font = self.resolve(font);
+        let has = |font: usize| match &self.fonts[font] {
+            FontSlot::Vector(vector) => vector.font.lookup_glyph_index(c) != 0,
+            FontSlot::Bitmap(bitmap) => bitmap.has(c),
+            FontSlot::Removed => false,
+        };
+        if has(font) {
+            return font;
+        }
+        self.fallbacks[font].iter().copied().find(|&fallback| has(fallback)).unwrap_or(font)
+    }
+
+    fn line_metrics(&self, font: usize, scale: u32) -> LineMetrics {
+        let vector = match &self.fonts[font] {
+            FontSlot::Vector(vector) => vector,
+            FontSlot::Bitmap(bitmap) => return bitmap.line_metrics(scale),
+            FontSlot::Removed => return self.line_metrics(0, scale),
+        };
+        let px = scale as f32;
+        match vector.font.horizontal_line_metrics(px) {
+            Some(line) => LineMetrics {
+                ascent: line.ascent,
+                descent: line.descent,
+                gap: line.line_gap,
+            },
+            None => LineMetrics {
+                ascent: px,
+                descent: 0.0,
+                gap: 0.0,
+            },
+        }
+    }
+
+    // Shapes the string followed by the spans into items, in logical order. Each character is drawn
+    // with the first font in its style's chain that has it, and each stretch of characters sharing a
+    // style, font, and direction is shaped together. Returns the items and the number of chars.
+    fn items(&self, desc: &Text) -> (Vec<Item>, usize) {
+        let mut styles = Vec::with_capacity(desc.spans.len() + 1);
+        styles.push((
+            desc.string.as_str(),
+            Style {
+                scale: desc.scale,
+                color: desc.color,
+                font: desc.font.key(),
+            },
+        ));
+        for span in &desc.spans {
+            styles.push((
+                span.string.as_str(),
+                Style {
+                    scale: span.scale.unwrap_or(desc.scale),
+                    color: span.color.unwrap_or(desc.color),
+                    font: span.font.unwrap_or(desc.font).key(),
+                },
+            ));
+        }
+        let text: String = styles.iter().map(|&(string, _)| string).collect();
+        let chars: Vec<(char, usize)> = styles
+            .iter()
+            .enumerate()
+            .flat_map(|(style, &(string, _))| string.chars().map(move |c| (c, style)))
+            .collect();
+        let levels = shaping::levels(&text);
+        let mut fonts: Vec<usize> = Vec::with_capacity(chars.len());
+        for (index, &(c, style)) in chars.iter().enumerate() {
+            // Whitespace and marks stay with the character before them, so they don't split runs.
+            let font = match fonts.last() {
+                Some(&font)
+                    if chars[index - 1].1 == style
+                        && (c.is_whitespace() || canonical_combining_class(c) != 0) =>
+                {
+                    font
+                }
+                _ => self.pick(styles[style].1.font, c),
+            };
+            fonts.push(font);
+        }
+
+        let mut items = Vec::with_capacity(chars.len());
+        let mut start = 0;
+        while start < chars.len() {
+            let style = styles[chars[start].1].1;
+            let font = fonts[start];
+            if chars[start].0 == '\n' {
+                items.push(Item {
+                    glyph: Glyph::None,
+                    color: style.color,
+                    cluster: start,
+                    advance: 0.0,
+                    offset: Vector2::zero(),
+                    level: levels[start].0,
+                    paragraph: levels[start].1,
+                    whitespace: true,
+                    newline: true,
+                    metrics: self.line_metrics(font, style.scale),
+                });
+                start += 1;
+                continue;
+            }
+            let mut end = start + 1;
+            while end < chars.len()
+                && chars[end].0 != '\n'
+                && chars[end].1 == chars[start].1
+                && fonts[end] == font
+                && levels[end] == levels[start]
+            {
+                end += 1;
+            }
+            match &self.fonts[font] {
+                FontSlot::Bitmap(bitmap) => {
+                    let chars: Vec<ShapedChar> = (start..end)
+                        .map(|source| ShapedChar {
+                            c: chars[source].0,
+                            source,
+                        })
+                        .collect();
+                    bitmap.shape(&chars, style.scale, style.color, levels[start], &mut items);
+                }
+                _ => self.shape(&chars[start..end], start, font, style, levels[start], &mut items),
+            }
+            start = end;
+        }
+        (items, chars.len())
+    }
+
+    // Shapes a run of characters drawn with a vector font, where first is the index of the run's
+    // first char in the text.
+    fn shape(
+        &self,
+        chars: &[(char, usize)],
+        first: usize,
+        font: usize,
+        style: Style,
+        (level, paragraph): (u8, u8),
+        items: &mut Vec<Item>,
+    ) {
+        let string: String = chars.iter().map(|&(c, _)| c).collect();
+        let offsets: Vec<usize> = string.char_indices().map(|(offset, _)| offset).collect();
+        let face = match &self.fonts[font] {
+            FontSlot::Vector(vector) => &vector.face,
+            _ => return,
+        };
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(&string);
+        buffer.set_direction(if level % 2 == 1 {
+            Direction::RightToLeft
+        } else {
+            Direction::LeftToRight
+        });
+        buffer.guess_segment_properties();
+        let output = rustybuzz::shape(face, &[], buffer);
+
+        let metrics = self.line_metrics(font, style.scale);
+        let scale = style.scale as f32 / face.units_per_em() as f32;
+        let start = items.len();
+        for (info, position) in output.glyph_infos().iter().zip(output.glyph_positions()) {
+            // Clusters are byte offsets into the run.
+            let index = match offsets.binary_search(&(info.cluster as usize)) {
+                Ok(index) => index,
+                Err(index) => index - 1,
+            };
+            items.push(Item {
+                glyph: Glyph::Vector {
+                    font,
+                    id: info.glyph_id as u16,
+                    px: style.scale,
+                },
+                color: style.color,
+                cluster: first + index,
+                advance: position.x_advance as f32 * scale,
+                offset: Vector2::new(position.x_offset as f32 * scale, position.y_offset as f32 * scale),
+                level,
+                paragraph,
+                whitespace: chars[index].0.is_whitespace(),
+                newline: false,
+                metrics,
+            });
+        }
+        // Right to left runs are shaped in visual order, and are put back into logical order.
+        if level % 2 == 1 {
+            items[start..].reverse();
+        }
+    }
+
+    /// Shapes and lays out the text. Drawing and measuring share this so measurements always match
+    /// what's drawn.
+    fn layout(&self, desc: &Text) -> Layout {
+        let (items, chars) = self.items(desc);
+        layout::layout(desc, &items, chars, self.line_metrics(desc.font.key(), desc.scale))
+    }
+
+    /// Lays out and rasterizes the text into glyph sprites. Glyphs from distance field fonts are
+    /// written to sdf_quads, since they're drawn with a different shader path. Effects are written
+    /// in layers, each behind the one above it: shadows, then glows, then outlines, then glyphs.
+    /// Bitmap fonts are drawn straight from their pages, so shadows are the only effect they have.
+    /// The glyphs are kept in the atlas for the owner until release is called for it.
+    pub fn rasterize(
+        &mut self,
+        atlas: &mut TextureAtlas,
+        desc: &Text,
+        owner: usize,
+        quads: &mut Vec<Sprite>,
+        sdf_quads: &mut Vec<Sprite>,
+    ) {
+        self.uses += 1;
+        let layout = self.layout(desc);
+        let z = desc.pos.z;
+        let mut placements = Vec::with_capacity(layout.placed.len());
+        for placed in &layout.placed {
+            let origin = placed.origin + placed.item.offset;
+            match placed.item.glyph {
+                Glyph::Vector {
+                    font,
+                    id,
+                    px,
+                } if !placed.item.whitespace => placements.push(Placement {
+                    key: GlyphKey {
+                        font,
+                        glyph: id,
+                        px,
+                    },
+                    origin,
+                    color: placed.item.color,
+                }),
+                Glyph::Bitmap {
+                    uv,
+                    size,
+                } => {
+                    if let Some(shadow) = desc.shadow {
+                        let pos = (origin + shadow.offset).extend(z - EFFECT_DEPTH * 3.0);
+                        quads.push(Sprite::new(pos, size, Texture(uv), shadow.color, 0.0));
+                    }
+                    quads.push(Sprite::new(origin.extend(z), size, Texture(uv), placed.item.color, 0.0));
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(shadow) = desc.shadow {
+            // The shadow follows the outline if there is one, so it matches the text's silhouette.
+            for placement in &placements {
+                let effect = match desc.outline {
+                    Some(outline) if outline.width > 0 => self.outline_effect(placement, outline.width),
+                    _ => self.fill_effect(placement),
+                };
+                let pos = (placement.origin + shadow.offset).extend(z - EFFECT_DEPTH * 3.0);
+                self.push(atlas, (placement.key, effect), pos, shadow.color, quads, sdf_quads);
+            }
+        }
+        match desc.glow {
+            // Glows are soft, so they're always drawn from a bitmap at the text's size.
+            Some(glow) if glow.radius > 0 => {
+                for placement in &placements {
+                    let pos = placement.origin.extend(z - EFFECT_DEPTH * 2.0);
+                    let glyph = (placement.key, Effect::Glow(glow.radius));
+                    self.push(atlas, glyph, pos, glow.color, quads, sdf_quads);
+                }
+            }
+            _ => {}
+        }
+        match desc.outline {
+            Some(outline) if outline.width > 0 => {
+                for placement in &placements {
+                    let pos = placement.origin.extend(z - EFFECT_DEPTH);
+                    let glyph = (placement.key, self.outline_effect(placement, outline.width));
+                    self.push(atlas, glyph, pos, outline.color, quads, sdf_quads);
+                }
+            }
+            _ => {}
+        }
+        for placement in &placements {
+            let pos = placement.origin.extend(z);
+            let glyph = (placement.key, self.fill_effect(placement));
+            self.push(atlas, glyph, pos, placement.color, quads, sdf_quads);
+        }
+        let pending = std::mem::take(&mut self.pending);
+        self.owners.entry(owner).or_default().extend(pending);
+    }
+
+    /// Rasterizes the given characters at each size ahead of time, so text drawn with them later
+    /// doesn't stall on rasterization. Pre-warmed glyphs aren't kept for any owner, so they can be
+    /// evicted once the glyph region fills up if they go unused.
+    pub fn prewarm(&mut self, atlas: &mut TextureAtlas, font: usize, chars: &str, sizes: &[u32]) {
+        let font = self.resolve(font);
+        let (glyphs, sdf) = match &self.fonts[font] {
+            FontSlot::Vector(vector) => {
+                let glyphs: Vec<u16> = chars
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .map(|c| vector.font.lookup_glyph_index(c))
+                    .collect();
+                (glyphs, vector.sdf)
+            }
+            // Bitmap fonts are already in the atlas.
+            _ => return,
         };
-        let styles = &[&TextStyle::new(&desc.string, desc.scale as f32, font_index)];
-
-        let mut positions = Vec::new();
-        layout.layout_horizontal(self.fonts.as_slice(), styles, &settings, &mut positions);
-        for &position in &positions {
-            let value = match self.cache.get(&position.key).copied() {
-                Some(value) => value,
-                None => {
-                    let (metrics, bitmap) = font.rasterize_config(position.key);
-                    let bitmap = Self::alpha_to_rgba(&bitmap);
-                    let texture = Image::from_vec(bitmap, metrics.width as u32, metrics.height as u32);
-                    let value = CharCacheValue {
-                        uv: atlas.add(texture),
-                        size: Vector2::new(metrics.width as f32, metrics.height as f32),
+        for &size in sizes {
+            // Each size counts as a use, so the first sizes are evicted first if they don't all fit.
+            self.uses += 1;
+            for &glyph in &glyphs {
+                let key = GlyphKey {
+                    font,
+                    glyph,
+                    px: size,
+                };
+                if sdf {
+                    let key = GlyphKey {
+                        px: SDF_SIZE as u32,
+                        ..key
                     };
-                    self.cache.insert(position.key, value);
-                    value
+                    self.glyph(atlas, key, Effect::Sdf);
+                } else {
+                    self.glyph(atlas, key, Effect::Plain);
+                }
+                // Unpinned right away, so glyphs pre-warmed earlier in the call can be evicted.
+                for slot in std::mem::take(&mut self.pending) {
+                    self.unpin(slot);
+                }
+            }
+        }
+    }
+
+    /// Releases the glyphs kept for the owner, letting them be evicted.
+    pub fn release(&mut self, owner: usize) {
+        if let Some(slots) = self.owners.remove(&owner) {
+            for slot in slots {
+                self.unpin(slot);
+            }
+        }
+    }
+
+    fn unpin(&mut self, slot: Slot) {
+        if let Some(count) = self.refs.get_mut(&slot) {
+            *count -= 1;
+            if *count == 0 {
+                self.refs.remove(&slot);
+                if let Some(index) = self.orphans.iter().position(|&orphan| orphan == slot) {
+                    self.orphans.swap_remove(index);
+                    self.free_slot(slot);
                 }
+            }
+        }
+    }
+
+    // Frees a slot dropped from the cache, or defers it until it's released if it's in use.
+    fn free_slot(&mut self, slot: Slot) {
+        if self.refs.contains_key(&slot) {
+            self.orphans.push(slot);
+        } else if let Some((_, allocator)) = &mut self.region {
+            allocator.free(slot);
+        }
+    }
+
+    // Gets the vector font at the given index. Glyphs are only cached for vector fonts.
+    fn vector(&self, font: usize) -> &Font {
+        match &self.fonts[font] {
+            FontSlot::Vector(vector) => &vector.font,
+            _ => panic!("Font {} isn't a vector font.", font),
+        }
+    }
+
+    fn is_sdf(&self, font: usize) -> bool {
+        match &self.fonts[font] {
+            FontSlot::Vector(vector) => vector.sdf,
+            _ => false,
+        }
+    }
+
+    fn fill_effect(&self, placement: &Placement) -> Effect {
+        if self.is_sdf(placement.key.font) {
+            Effect::Sdf
+        } else {
+            Effect::Plain
+        }
+    }
+
+    fn outline_effect(&self, placement: &Placement, width: u32) -> Effect {
+        if self.is_sdf(placement.key.font) {
+            // Distance fields can only be grown as far as they spread.
+            let width = width as f32 * SDF_SIZE / placement.key.px as f32;
+            let width = width.min(SDF_SPREAD as f32 - 1.0);
+            Effect::SdfOutline((width * 4.0).round() as u32)
+        } else {
+            Effect::Outline(width)
+        }
+    }
+
+    /// Writes a sprite for a glyph with the given effect. The position is the glyph's origin.
+    fn push(
+        &mut self,
+        atlas: &mut TextureAtlas,
+        (key, effect): (GlyphKey, Effect),
+        pos: Vector3<f32>,
+        color: RGBA8,
+        quads: &mut Vec<Sprite>,
+        sdf_quads: &mut Vec<Sprite>,
+    ) {
+        if effect.is_sdf() {
+            // Distance field glyphs are rasterized once at SDF_SIZE and scaled to each size.
+            let scale = key.px as f32 / SDF_SIZE;
+            let key = GlyphKey {
+                px: SDF_SIZE as u32,
+                ..key
             };
+            let value = self.glyph(atlas, key, effect);
+            let size = value.size * scale;
+            sdf_quads.push(Sprite::new(
+                pos + (value.offset * scale).extend(0.0),
+                Vector2::new(size.x.round(), size.y.round()),
+                Texture(value.uv),
+                color,
+                0.0,
+            ));
+        } else {
+            let value = self.glyph(atlas, key, effect);
             quads.push(Sprite::new(
-                Vector3::new(position.x, position.y, desc.pos.z),
+                pos + value.offset.extend(0.0),
                 value.size,
                 Texture(value.uv),
-                desc.color,
+                color,
                 0.0,
             ));
         }
     }
 
+    /// Gets a glyph from the cache, rasterizing it into the atlas if it isn't cached yet.
+    fn glyph(&mut self, atlas: &mut TextureAtlas, key: GlyphKey, effect: Effect) -> CharCacheValue {
+        let uses = self.uses;
+        if let Some(value) = self.cache.get_mut(&(key, effect)) {
+            value.last_used = uses;
+            if let Some(slot) = value.slot {
+                *self.refs.entry(slot).or_insert(0) += 1;
+                self.pending.push(slot);
+            }
+            return *value;
+        }
+        let (metrics, bitmap) = self.vector(key.font).rasterize_indexed(key.glyph, key.px as f32);
+        let (width, height) = (metrics.width as u32, metrics.height as u32);
+        let (pixels, width, height, padding) = match effect {
+            Effect::Plain => (Self::alpha_to_rgba(&bitmap), width, height, 0),
+            Effect::Sdf | Effect::SdfOutline(_) => {
+                let (mut field, width, height) = sdf::generate(&bitmap, width, height);
+                if let Effect::SdfOutline(quarters) = effect {
+                    let amount = (quarters as f32 / 4.0) / (SDF_SPREAD as f32 * 2.0);
+                    field = effect::grow_field(&field, amount);
+                }
+                (Self::alpha_to_field(&field), width, height, SDF_SPREAD)
+            }
+            Effect::Outline(radius) => {
+                let (bitmap, width, height) = effect::outline(&bitmap, width, height, radius);
+                (Self::alpha_to_rgba(&bitmap), width, height, radius)
+            }
+            Effect::Glow(radius) => {
+                let (bitmap, width, height) = effect::glow(&bitmap, width, height, radius);
+                (Self::alpha_to_rgba(&bitmap), width, height, radius)
+            }
+        };
+        let padding = padding as f32;
+        let (uv, slot) = self.store(atlas, Image::from_vec(pixels, width, height));
+        if let Some(slot) = slot {
+            self.refs.insert(slot, 1);
+            self.pending.push(slot);
+        }
+        let value = CharCacheValue {
+            uv,
+            size: Vector2::new(width as f32, height as f32),
+            offset: Vector2::new(metrics.xmin as f32 - padding, metrics.ymin as f32 - padding),
+            slot,
+            last_used: self.uses,
+        };
+        self.cache.insert((key, effect), value);
+        value
+    }
+
+    /// Places a glyph's pixels in the glyph region, evicting the least recently used glyphs that
+    /// aren't in use until it fits. Glyphs that still don't fit are added to the atlas permanently.
+    fn store(&mut self, atlas: &mut TextureAtlas, image: Image) -> (Vector4<u16>, Option<Slot>) {
+        if self.region.is_none() {
+            self.region = atlas
+                .reserve(GLYPH_REGION, GLYPH_REGION)
+                .map(|origin| (origin, ShelfAllocator::new(GLYPH_REGION, GLYPH_REGION)));
+        }
+        let (origin, allocator) = match &mut self.region {
+            Some(region) => region,
+            None => return (atlas.add(image), None),
+        };
+        // A pixel of transparent padding on each side keeps filtering from sampling neighbors.
+        let (width, height) = (image.width() + 2, image.height() + 2);
+        let mut slot = allocator.alloc(width, height);
+        if slot.is_none() {
+            let refs = &self.refs;
+            let mut unused: Vec<(u64, (GlyphKey, Effect))> = self
+                .cache
+                .iter()
+                .filter(|(_, value)| value.slot.is_some_and(|slot| !refs.contains_key(&slot)))
+                .map(|(&key, value)| (value.last_used, key))
+                .collect();
+            unused.sort_by_key(|&(last_used, _)| last_used);
+            for (_, key) in unused {
+                if let Some(CharCacheValue {
+                    slot: Some(evicted),
+                    ..
+                }) = self.cache.remove(&key)
+                {
+                    allocator.free(evicted);
+                }
+                slot = allocator.alloc(width, height);
+                if slot.is_some() {
+                    break;
+                }
+            }
+        }
+        match slot {
+            Some(slot) => {
+                let (x, y) = (origin.0 + slot.x, origin.1 + slot.y);
+                atlas.set_pixels(x, y, &Image::from_color(TRANSPARENT, width, height));
+                (atlas.set_pixels(x + 1, y + 1, &image), Some(slot))
+            }
+            None => {
+                warn!("The glyph region is full of glyphs in use. Adding the glyph to the atlas instead.");
+                (atlas.add(image), None)
+            }
+        }
+    }
+
+    pub fn measure(&self, desc: &Text) -> TextMetrics {
+        let layout = self.layout(desc);
+        let chars: Vec<char> =
+            desc.string.chars().chain(desc.spans.iter().flat_map(|span| span.string.chars())).collect();
+        let mut glyphs = Vec::with_capacity(layout.placed.len());
+        let mut min = Vector2::new(f32::INFINITY, f32::INFINITY);
+        let mut max = Vector2::new(f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for placed in &layout.placed {
+            let item = &placed.item;
+            if item.newline {
+                continue;
+            }
+            let line = &layout.lines[placed.line];
+            min.x = min.x.min(placed.origin.x);
+            min.y = min.y.min(line.baseline + line.metrics.descent);
+            max.x = max.x.max(placed.origin.x + item.advance);
+            max.y = max.y.max(line.baseline + line.metrics.ascent);
+            let origin = placed.origin + item.offset;
+            let (pos, size) = match item.glyph {
+                Glyph::Vector {
+                    font,
+                    id,
+                    px,
+                } if !item.whitespace => {
+                    let metrics = self.vector(font).metrics_indexed(id, px as f32);
+                    (
+                        origin + Vector2::new(metrics.xmin as f32, metrics.ymin as f32),
+                        Vector2::new(metrics.width as f32, metrics.height as f32),
+                    )
+                }
+                Glyph::Bitmap {
+                    size,
+                    ..
+                } => (origin, size),
+                _ => (origin, Vector2::zero()),
+            };
+            glyphs.push(GlyphMetrics {
+                character: chars[item.cluster],
+                index: item.cluster,
+                line: placed.line,
+                pos,
+                size,
+            });
+        }
+
+        if glyphs.is_empty() {
+            min = Vector2::new(desc.pos.x, desc.pos.y);
+            max = min;
+        }
+        TextMetrics {
+            pos: min,
+            size: max - min,
+            lines: if glyphs.is_empty() {
+                0
+            } else {
+                layout.lines.len()
+            },
+            ascent: layout.lines.iter().map(|line| line.metrics.ascent).fold(0.0, f32::max),
+            descent: layout.lines.iter().map(|line| line.metrics.descent).fold(0.0, f32::min),
+            glyphs,
+            carets: layout.carets,
+        }
+    }
+
+    fn alpha_to_field(field: &[u8]) -> Vec<RGBA8> {
+        field.iter().map(|&v| RGBA8::new_raw(255, 255, 255, v)).collect()
+    }
+
     fn alpha_to_rgba(bitmap: &Vec<u8>) -> Vec<RGBA8> {
         let mut output = Vec::with_capacity(bitmap.len());
         for v in bitmap {
@@ -101,3 +829,395 @@ impl TextCache {
         output
     }
 }
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+
+    // Covers the Devanagari letter ka and the vowel sign i.
+    static DEVANAGARI: &[u8] = include_bytes!("fonts/test/NotoSansDevanagari-Subset.ttf");
+    // Covers space, A, B, and the Hebrew letters alef and bet.
+    static HEBREW: &[u8] = include_bytes!("fonts/test/TestMORXForty.ttf");
+
+    fn text(string: &str) -> Text {
+        Text {
+            string: String::from(string),
+            pos: Vector3::new(0.0, 100.0, 0.0),
+            horizontal_align: HorizontalAlign::Left,
+            ..Text::default()
+        }
+    }
+
+    fn width(cache: &TextCache, string: &str) -> f32 {
+        cache.measure(&text(string)).size.x
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 0.001, "{} isn't {}", actual, expected);
+    }
+
+    #[test]
+    fn horizontal_alignment() {
+        let cache = TextCache::new();
+        let mut desc = text("Hello");
+        let width = cache.measure(&desc).size.x;
+        assert_near(cache.measure(&desc).pos.x, 0.0);
+        // Without a max width, lines are aligned around the position.
+        desc.horizontal_align = HorizontalAlign::Center;
+        assert_near(cache.measure(&desc).pos.x, -width / 2.0);
+        desc.horizontal_align = HorizontalAlign::Right;
+        assert_near(cache.measure(&desc).pos.x, -width);
+
+        desc.max_width = Some(200.0);
+        desc.horizontal_align = HorizontalAlign::Left;
+        assert_near(cache.measure(&desc).pos.x, 0.0);
+        desc.horizontal_align = HorizontalAlign::Center;
+        assert_near(cache.measure(&desc).pos.x, (200.0 - width) / 2.0);
+        desc.horizontal_align = HorizontalAlign::Right;
+        assert_near(cache.measure(&desc).pos.x, 200.0 - width);
+    }
+
+    #[test]
+    fn vertical_alignment() {
+        let cache = TextCache::new();
+        let line = cache.line_metrics(0, 24);
+        let mut desc = text("Hello");
+        // Without a max height, the bottom of the text sits on the position.
+        for &align in &[VerticalAlign::Top, VerticalAlign::Middle, VerticalAlign::Bottom] {
+            desc.vertical_align = align;
+            let metrics = cache.measure(&desc);
+            assert_near(metrics.pos.y, 100.0 + line.gap);
+            assert_near(metrics.size.y, line.ascent - line.descent);
+        }
+
+        // The box extends up from the position.
+        desc.max_height = Some(100.0);
+        desc.vertical_align = VerticalAlign::Top;
+        let metrics = cache.measure(&desc);
+        assert_near(metrics.pos.y + metrics.size.y, 200.0);
+        desc.vertical_align = VerticalAlign::Middle;
+        let metrics = cache.measure(&desc);
+        assert_near(metrics.pos.y + metrics.size.y, 100.0 + (100.0 + line.height()) / 2.0);
+        desc.vertical_align = VerticalAlign::Bottom;
+        let metrics = cache.measure(&desc);
+        assert_near(metrics.pos.y, 100.0 + line.gap);
+
+        // Lines stack down from the top of the text.
+        desc.string = String::from("Hello\nHello");
+        desc.line_spacing = 2.0;
+        let metrics = cache.measure(&desc);
+        assert_eq!(metrics.lines, 2);
+        assert_near(metrics.pos.y, 100.0 + line.height() + line.gap);
+        assert_near(metrics.carets[0].pos.y - metrics.carets[6].pos.y, line.height() * 2.0);
+    }
+
+    #[test]
+    fn measure() {
+        let cache = TextCache::new();
+        let mut desc = text("aa\na");
+        desc.spans.push(TextSpan {
+            scale: Some(48),
+            ..TextSpan::new("a")
+        });
+        let metrics = cache.measure(&desc);
+        assert_eq!(metrics.lines, 2);
+        // Repeated characters keep their own index, and the line break isn't drawn.
+        let glyphs: Vec<(usize, usize)> =
+            metrics.glyphs.iter().map(|glyph| (glyph.index, glyph.line)).collect();
+        assert_eq!(glyphs, [(0, 0), (1, 0), (3, 1), (4, 1)]);
+        assert!(metrics.glyphs.iter().all(|glyph| glyph.character == 'a'));
+        assert!(metrics.glyphs[0].pos.x < metrics.glyphs[1].pos.x);
+        assert_eq!(metrics.glyphs[0].pos.x, metrics.glyphs[2].pos.x);
+        // The span is drawn larger, on the same line.
+        assert!(metrics.glyphs[3].size.y > metrics.glyphs[2].size.y * 1.5);
+        assert_eq!(metrics.ascent, cache.line_metrics(0, 48).ascent);
+        assert_eq!(metrics.descent, cache.line_metrics(0, 48).descent);
+        assert_near(metrics.pos.x, 0.0);
+        assert_near(metrics.pos.x + metrics.size.x, metrics.carets[5].pos.x);
+
+        // Lines come from the layout, even when wrapped lines share a height.
+        let mut desc = text("aa aa");
+        desc.max_width = Some(width(&cache, "aa a"));
+        let metrics = cache.measure(&desc);
+        assert_eq!(metrics.lines, 2);
+        let lines: Vec<usize> = metrics.glyphs.iter().map(|glyph| glyph.line).collect();
+        assert_eq!(lines, [0, 0, 0, 1, 1]);
+        assert_eq!(metrics.carets[3].line, 1);
+        assert_near(metrics.carets[3].pos.x, 0.0);
+
+        // Empty text has no lines, but still has a caret.
+        let metrics = cache.measure(&text(""));
+        assert_eq!(metrics.lines, 0);
+        assert_eq!(metrics.size, Vector2::zero());
+        assert_eq!(metrics.carets.len(), 1);
+    }
+
+    #[test]
+    fn caret_at() {
+        let cache = TextCache::new();
+        let metrics = cache.measure(&text("ab\ncd"));
+        let carets: Vec<Vector2<f32>> = metrics.carets.iter().map(|caret| caret.pos).collect();
+        assert_eq!(carets.len(), 6);
+        for (index, &caret) in carets.iter().enumerate() {
+            assert_eq!(metrics.caret_at(caret), index);
+        }
+        // Points between carets pick the closer one.
+        let quarter = carets[0] + (carets[1] - carets[0]) * 0.25;
+        assert_eq!(metrics.caret_at(quarter), 0);
+        let three_quarters = carets[0] + (carets[1] - carets[0]) * 0.75;
+        assert_eq!(metrics.caret_at(three_quarters), 1);
+        // Points off the text pick the closest line, then the closest caret on it.
+        assert_eq!(metrics.caret_at(Vector2::new(carets[1].x, 1000.0)), 1);
+        assert_eq!(metrics.caret_at(Vector2::new(carets[4].x, -1000.0)), 4);
+        assert_eq!(metrics.caret_at(Vector2::new(1000.0, carets[0].y)), 2);
+        assert_eq!(metrics.caret_at(Vector2::new(-1000.0, carets[5].y)), 3);
+    }
+
+    #[test]
+    fn eviction() {
+        let mut cache = TextCache::new();
+        let mut atlas = TextureAtlas::new();
+        let (mut quads, mut sdf_quads) = (Vec::new(), Vec::new());
+        cache.rasterize(&mut atlas, &text("Pinned"), 1, &mut quads, &mut sdf_quads);
+        let pinned: Vec<((GlyphKey, Effect), Vector4<u16>)> =
+            cache.cache.iter().map(|(&key, value)| (key, value.uv)).collect();
+        assert!(!pinned.is_empty());
+
+        // Far more glyphs than fit in the glyph region.
+        let letters = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+        let sizes: Vec<u32> = (40..160).step_by(4).collect();
+        cache.prewarm(&mut atlas, 0, letters, &sizes);
+        let area: u32 =
+            cache.cache.values().filter_map(|value| value.slot).map(|slot| slot.width * slot.height).sum();
+        assert!(area <= GLYPH_REGION * GLYPH_REGION);
+        // The least recently used glyphs were evicted to make room, rather than spilling into the
+        // rest of the atlas.
+        assert!(cache.cache.len() < pinned.len() + letters.len() * sizes.len());
+        assert!(cache.cache.values().all(|value| value.slot.is_some()));
+        let first = GlyphKey {
+            font: 0,
+            glyph: cache.vector(0).lookup_glyph_index('A'),
+            px: sizes[0],
+        };
+        assert!(!cache.cache.contains_key(&(first, Effect::Plain)));
+
+        // Glyphs in use are never evicted, and nothing was placed over them.
+        for (key, uv) in &pinned {
+            assert_eq!(cache.cache.get(key).map(|value| value.uv), Some(*uv));
+        }
+        let slots: Vec<Slot> = cache.cache.values().filter_map(|value| value.slot).collect();
+        for (index, a) in slots.iter().enumerate() {
+            for b in &slots[index + 1..] {
+                let apart = a.x + a.width <= b.x
+                    || b.x + b.width <= a.x
+                    || a.y + a.height <= b.y
+                    || b.y + b.height <= a.y;
+                assert!(apart, "{:?} overlaps {:?}", a, b);
+            }
+        }
+
+        // Once released, they're evicted like any other glyph.
+        cache.release(1);
+        cache.prewarm(&mut atlas, 0, letters, &sizes);
+        assert!(pinned.iter().any(|(key, _)| !cache.cache.contains_key(key)));
+    }
+
+    #[test]
+    fn load_bytes_errors() {
+        let mut cache = TextCache::new();
+        assert!(cache.add_font_bytes(b"not a font", false).is_err());
+        assert!(cache.add_font_bytes(&DEVANAGARI[..64], false).is_err());
+        assert!(cache.replace_font_bytes(0, b"not a font", false).is_err());
+        // Failed loads leave the loaded fonts alone.
+        assert_eq!(cache.fonts.len(), 1);
+        assert_eq!(cache.add_font_bytes(HEBREW, false), Ok(1));
+        assert!(width(&cache, "fi") > 0.0);
+    }
+
+    #[test]
+    fn remove() {
+        let mut cache = TextCache::new();
+        let font = cache.add_font_bytes(HEBREW, false).unwrap();
+        let mut desc = text("AB");
+        desc.font = FontToken::new(font);
+        let loaded = cache.measure(&desc);
+        cache.remove_font(font);
+        let removed = cache.measure(&desc);
+        assert_ne!(removed.size, loaded.size);
+        // Text using the removed font is drawn with the default font.
+        assert_eq!(removed.size, cache.measure(&text("AB")).size);
+
+        // Removed fonts are skipped in fallback chains.
+        let other = cache.add_font_bytes(DEVANAGARI, false).unwrap();
+        cache.set_fallbacks(other, &[font]);
+        assert_eq!(cache.pick(other, 'A'), other);
+    }
+
+    #[test]
+    fn set_default() {
+        let mut cache = TextCache::new();
+        let font = cache.add_font_bytes(DEVANAGARI, false).unwrap();
+        let roboto = cache.measure(&text("AB"));
+        cache.remove_font(font);
+        cache.replace_font_bytes(0, HEBREW, false).unwrap();
+        let hebrew = cache.measure(&text("AB"));
+        assert_ne!(hebrew.size, roboto.size);
+        // Text using a removed font follows the default font when it's replaced.
+        let mut desc = text("AB");
+        desc.font = FontToken::new(font);
+        assert_eq!(cache.measure(&desc).size, hebrew.size);
+    }
+
+    #[test]
+    fn ligatures() {
+        let cache = TextCache::new();
+        let metrics = cache.measure(&text("fi"));
+        // Both chars are drawn with one glyph, but still get a caret each.
+        assert_eq!(metrics.glyphs.len(), 1);
+        assert_eq!(metrics.glyphs[0].index, 0);
+        assert_eq!(metrics.carets.len(), 3);
+        assert!(metrics.carets[0].pos.x < metrics.carets[1].pos.x);
+        assert!(metrics.carets[1].pos.x < metrics.carets[2].pos.x);
+        assert_eq!(metrics.carets[2].pos.x, metrics.size.x);
+    }
+
+    // The font drawing each item of the laid out text, in logical order.
+    fn fonts(cache: &TextCache, desc: &Text) -> Vec<usize> {
+        let (items, _) = cache.items(desc);
+        items
+            .iter()
+            .map(|item| match item.glyph {
+                Glyph::Vector {
+                    font,
+                    ..
+                } => font,
+                glyph => panic!("Expected a vector glyph, got {:?}.", glyph),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fallback_chain() {
+        let mut cache = TextCache::new();
+        let devanagari = cache.add_font_bytes(DEVANAGARI, false).unwrap();
+        let hebrew = cache.add_font_bytes(HEBREW, false).unwrap();
+        // Ka, then a space and A, neither of which the Devanagari font has.
+        let mut desc = text("\u{0915} A");
+        desc.font = FontToken::new(devanagari);
+
+        // Without fallbacks, missing characters are drawn with the font's notdef glyph.
+        assert_eq!(fonts(&cache, &desc), [devanagari, devanagari, devanagari]);
+        // The first font in the chain with the character draws it. Whitespace stays with the
+        // character before it.
+        cache.set_fallbacks(devanagari, &[hebrew, 0]);
+        assert_eq!(fonts(&cache, &desc), [devanagari, devanagari, hebrew]);
+        cache.set_fallbacks(devanagari, &[0, hebrew]);
+        assert_eq!(fonts(&cache, &desc), [devanagari, devanagari, 0]);
+        // Alef is only in the Hebrew font, so it's found further down the chain.
+        desc.string = String::from("\u{0915}\u{05D0}");
+        assert_eq!(fonts(&cache, &desc), [devanagari, hebrew]);
+        // Chains aren't followed recursively.
+        cache.set_fallbacks(devanagari, &[0]);
+        cache.set_fallbacks(0, &[hebrew]);
+        assert_eq!(fonts(&cache, &desc), [devanagari, devanagari]);
+    }
+
+    #[test]
+    fn bitmap_fallback() {
+        let mut cache = TextCache::new();
+        let mut bitmap = BitmapFont::parse(
+            b"info size=8\n\
+              common lineHeight=10 base=8 scaleW=64 scaleH=64 pages=1\n\
+              page id=0 file=\"font.png\"\n\
+              char id=65 x=0 y=0 width=5 height=8 xoffset=0 yoffset=0 xadvance=6 page=0\n",
+        )
+        .unwrap();
+        bitmap.set_page_uvs(vec![Vector4::new(0, 64 * PIXEL_SIZE as u16, 0, 64 * PIXEL_SIZE as u16)]);
+        let font = cache.add_bitmap_font(bitmap);
+        cache.set_fallbacks(font, &[0]);
+        let mut desc = text("AB");
+        desc.font = FontToken::new(font);
+        desc.scale = 16;
+
+        // A is drawn from the bitmap font, and B from the vector font after it.
+        let layout = cache.layout(&desc);
+        assert_eq!(layout.placed.len(), 2);
+        match layout.placed[0].item.glyph {
+            Glyph::Bitmap {
+                size,
+                ..
+            } => assert_eq!(size, Vector2::new(10.0, 16.0)),
+            glyph => panic!("Expected a bitmap glyph, got {:?}.", glyph),
+        }
+        match layout.placed[1].item.glyph {
+            Glyph::Vector {
+                font,
+                id,
+                ..
+            } => {
+                assert_eq!(font, 0);
+                assert_eq!(id, cache.vector(0).lookup_glyph_index('B'));
+            }
+            glyph => panic!("Expected a vector glyph, got {:?}.", glyph),
+        }
+        assert_eq!(layout.placed[1].origin.x, 12.0);
+    }
+
+    #[test]
+    fn kerning() {
+        let cache = TextCache::new();
+        assert!(width(&cache, "AV") < width(&cache, "A") + width(&cache, "V"));
+    }
+
+    #[test]
+    fn indic_reordering() {
+        let mut cache = TextCache::new();
+        let font = cache.add_font_bytes(DEVANAGARI, false).unwrap();
+        let mut desc = text("\u{0915}\u{093F}");
+        desc.font = FontToken::new(font);
+        let metrics = cache.measure(&desc);
+        // The vowel sign i is written after ka, but drawn before it, in the same cluster.
+        assert_eq!(metrics.glyphs.len(), 2);
+        assert!(metrics.glyphs.iter().all(|glyph| glyph.index == 0 && glyph.character == '\u{0915}'));
+        assert!(metrics.glyphs[0].size.x < metrics.glyphs[1].size.x);
+        assert!(metrics.carets[0].pos.x < metrics.carets[1].pos.x);
+    }
+
+    #[test]
+    fn right_to_left_wrapping() {
+        let mut cache = TextCache::new();
+        let font = cache.add_font_bytes(HEBREW, false).unwrap();
+        // Alef bet, then bet alef, which only fit on a line one word at a time.
+        let mut desc = text("\u{05D0}\u{05D1} \u{05D1}\u{05D0}");
+        desc.font = FontToken::new(font);
+        let one_line = cache.measure(&desc);
+        assert_eq!(one_line.lines, 1);
+        desc.max_width = Some(one_line.size.x * 0.75);
+        let metrics = cache.measure(&desc);
+        assert_eq!(metrics.lines, 2);
+
+        // The first word is on the top line, read from the right.
+        let line = |line: usize| -> Vec<usize> {
+            metrics
+                .glyphs
+                .iter()
+                .filter(|glyph| glyph.line == line && glyph.character != ' ')
+                .map(|glyph| glyph.index)
+                .collect()
+        };
+        assert_eq!(line(0), [1, 0]);
+        assert_eq!(line(1), [4, 3]);
+        // Carets run from right to left within each line.
+        assert_eq!(metrics.carets[0].line, 0);
+        assert!(metrics.carets[0].pos.x > metrics.carets[1].pos.x);
+        assert!(metrics.carets[1].pos.x > metrics.carets[2].pos.x);
+        assert_eq!(metrics.carets[3].line, 1);
+        assert!(metrics.carets[3].pos.x > metrics.carets[4].pos.x);
+        assert!(metrics.carets[4].pos.x > metrics.carets[5].pos.x);
+    }
+}
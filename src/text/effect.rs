@@ -0,0 +1,92 @@
+/// Thickens a coverage bitmap by the given radius. The output is padded by the radius on each side,
+/// and the edge of the outline is anti-aliased.
+pub fn outline(bitmap: &[u8], width: u32, height: u32, radius: u32) -> (Vec<u8>, u32, u32) {
+    let r = radius as f32;
+    spread(bitmap, width, height, radius, |distance| (r + 0.5 - distance).clamp(0.0, 1.0))
+}
+
+/// Blurs a coverage bitmap outward by the given radius, fading out with distance from the glyph.
+/// The output is padded by the radius on each side.
+pub fn glow(bitmap: &[u8], width: u32, height: u32, radius: u32) -> (Vec<u8>, u32, u32) {
+    let r = radius as f32 + 1.0;
+    spread(bitmap, width, height, radius, |distance| {
+        let falloff = (1.0 - distance / r).max(0.0);
+        falloff * falloff
+    })
+}
+
+/// Grows a signed distance field outward by the given amount, where 1.0 is the full range of the
+/// field. Drawing the result with the same threshold gives an outline around the original glyph.
+pub fn grow_field(field: &[u8], amount: f32) -> Vec<u8> {
+    let shift = amount * 255.0;
+    field.iter().map(|&v| (v as f32 + shift).clamp(0.0, 255.0).round() as u8).collect()
+}
+
+// Each output pixel takes the strongest contribution of the surrounding pixels, weighted by the
+// falloff of their distance.
+fn spread<F: Fn(f32) -> f32>(
+    bitmap: &[u8],
+    width: u32,
+    height: u32,
+    radius: u32,
+    falloff: F,
+) -> (Vec<u8>, u32, u32) {
+    let r = radius as i32;
+    let out_width = width as i32 + r * 2;
+    let out_height = height as i32 + r * 2;
+    let coverage = |x: i32, y: i32| {
+        let x = x - r;
+        let y = y - r;
+        if x >= 0 && y >= 0 && x < width as i32 && y < height as i32 {
+            bitmap[(y * width as i32 + x) as usize] as f32
+        } else {
+            0.0
+        }
+    };
+
+    let mut output = Vec::with_capacity((out_width * out_height) as usize);
+    for y in 0..out_height {
+        for x in 0..out_width {
+            let mut value = 0.0f32;
+            for dy in -(r + 1)..=(r + 1) {
+                for dx in -(r + 1)..=(r + 1) {
+                    let weight = falloff(((dx * dx + dy * dy) as f32).sqrt());
+                    if weight > 0.0 {
+                        value = value.max(coverage(x + dx, y + dy) * weight);
+                    }
+                }
+            }
+            output.push(value.round() as u8);
+        }
+    }
+    (output, out_width as u32, out_height as u32)
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn effects() {
+        // A single covered pixel.
+        let (output, width, height) = outline(&[255], 1, 1, 2);
+        assert_eq!((width, height), (5, 5));
+        assert_eq!(output[2 * 5 + 2], 255);
+        assert_eq!(output[2 * 5 + 1], 255);
+        assert_eq!(output[2 * 5], 128);
+        assert_eq!(output[0], 0);
+
+        let (output, width, height) = glow(&[255], 1, 1, 2);
+        assert_eq!((width, height), (5, 5));
+        assert_eq!(output[2 * 5 + 2], 255);
+        assert!(output[2 * 5 + 1] > output[2 * 5]);
+        assert!(output[2 * 5] > 0);
+
+        assert_eq!(grow_field(&[0, 128, 250], 0.25), vec![64, 192, 255]);
+    }
+}
@@ -0,0 +1,332 @@
+use crate::text::shaping;
+use crate::*;
+use cgmath::*;
+use std::ops::Range;
+
+/// What a laid out item draws.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Glyph {
+    /// A glyph from a vector font, by its index in the font, rasterized at px.
+    Vector {
+        font: usize,
+        id: u16,
+        px: u32,
+    },
+    /// A character from a bitmap font, drawn straight from its page.
+    Bitmap {
+        uv: Vector4<u16>,
+        size: Vector2<f32>,
+    },
+    /// Nothing is drawn, like for line breaks.
+    None,
+}
+
+/// Vertical metrics of the font an item is drawn with. Units are measured in pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LineMetrics {
+    /// Distance from the baseline to the top of the line.
+    pub ascent: f32,
+    /// Distance from the baseline to the bottom of the line. This is usually negative.
+    pub descent: f32,
+    /// Space left between the bottom of the line and the top of the next.
+    pub gap: f32,
+}
+
+impl LineMetrics {
+    /// Distance from the top of the line to the top of the next.
+    pub fn height(&self) -> f32 {
+        self.ascent - self.descent + self.gap
+    }
+}
+
+/// A shaped glyph waiting to be laid out. Items are given in logical order.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Item {
+    pub glyph: Glyph,
+    pub color: RGBA8,
+    /// Index of the first char of the cluster the glyph belongs to, in the string followed by the
+    /// spans. A cluster is the smallest group of chars shaping doesn't split, so every glyph drawn
+    /// for a ligature or a character with marks shares one.
+    pub cluster: usize,
+    /// Distance the pen moves after the glyph.
+    pub advance: f32,
+    /// Offset of what's drawn from the pen. For bitmap characters this is the bottom left of the
+    /// image.
+    pub offset: Vector2<f32>,
+    /// Bidirectional embedding level. Odd levels are right to left.
+    pub level: u8,
+    /// Embedding level of the paragraph the glyph is in.
+    pub paragraph: u8,
+    pub whitespace: bool,
+    pub newline: bool,
+    pub metrics: LineMetrics,
+}
+
+/// An item with its position.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Placed {
+    pub item: Item,
+    /// Position of the pen on the baseline before the item.
+    pub origin: Vector2<f32>,
+    pub line: usize,
+}
+
+/// A laid out line.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Line {
+    pub baseline: f32,
+    /// The tallest metrics of the items on the line.
+    pub metrics: LineMetrics,
+}
+
+/// Text laid out into lines.
+pub struct Layout {
+    /// The placed items, line by line, each line in visual order from left to right.
+    pub placed: Vec<Placed>,
+    pub lines: Vec<Line>,
+    /// One caret before each char, and one after the last.
+    pub carets: Vec<Caret>,
+}
+
+// The horizontal extent of a cluster's glyphs.
+#[derive(Copy, Clone)]
+struct Extent {
+    left: f32,
+    right: f32,
+    line: usize,
+    baseline: f32,
+    rtl: bool,
+}
+
+/// Lays out the items of text with `chars` characters. Lines are broken in logical order, then
+/// each line is reordered for display on its own, so right-to-left text that wraps reads from the
+/// top line down. Lines without any items are sized by the given metrics.
+pub fn layout(desc: &Text, items: &[Item], chars: usize, empty: LineMetrics) -> Layout {
+    let ranges = break_lines(desc, items);
+    let metrics: Vec<LineMetrics> =
+        ranges.iter().map(|range| line_metrics(&items[range.clone()], empty)).collect();
+    let total: f32 = metrics.iter().map(|metrics| metrics.height() * desc.line_spacing).sum();
+    // The position is the bottom left of the box the text is aligned within.
+    let mut top = desc.pos.y
+        + match (desc.max_height, desc.vertical_align) {
+            (Some(max_height), VerticalAlign::Top) => max_height,
+            (Some(max_height), VerticalAlign::Middle) => (max_height + total) / 2.0,
+            (Some(_), VerticalAlign::Bottom) | (None, _) => total,
+        };
+
+    let mut placed = Vec::with_capacity(items.len());
+    let mut lines = Vec::with_capacity(ranges.len());
+    let mut extents: Vec<Option<Extent>> = vec![None; chars];
+    let mut start = Vector2::new(desc.pos.x, top);
+    for (index, (range, &metrics)) in ranges.iter().zip(&metrics).enumerate() {
+        let line = &items[range.clone()];
+        let baseline = top - metrics.ascent;
+        // Per rule L1, whitespace at the end of the line and the line break take the paragraph's
+        // level. They're left out of the width, and trail off the end in the paragraph's direction.
+        let trailing = line.iter().rposition(|item| !item.whitespace && !item.newline).map_or(0, |i| i + 1);
+        let levels: Vec<u8> = line[..trailing].iter().map(|item| item.level).collect();
+        let width: f32 = line[..trailing].iter().map(|item| item.advance).sum();
+        let left = desc.pos.x
+            + match (desc.max_width, desc.horizontal_align) {
+                (_, HorizontalAlign::Left) => 0.0,
+                (Some(max_width), HorizontalAlign::Center) => (max_width - width) / 2.0,
+                (Some(max_width), HorizontalAlign::Right) => max_width - width,
+                (None, HorizontalAlign::Center) => -width / 2.0,
+                (None, HorizontalAlign::Right) => -width,
+            };
+        start = Vector2::new(left, baseline);
+
+        let first = placed.len();
+        let mut pen = left;
+        for index_in_line in shaping::visual_order(&levels) {
+            let item = line[index_in_line];
+            placed.push(Placed {
+                item,
+                origin: Vector2::new(pen, baseline),
+                line: index,
+            });
+            pen += item.advance;
+        }
+        let rtl = line.last().is_some_and(|item| item.paragraph % 2 == 1);
+        let mut before = left;
+        for &item in &line[trailing..] {
+            let item = Item {
+                level: item.paragraph,
+                ..item
+            };
+            if rtl {
+                before -= item.advance;
+                let origin = Vector2::new(before, baseline);
+                placed.insert(
+                    first,
+                    Placed {
+                        item,
+                        origin,
+                        line: index,
+                    },
+                );
+            } else {
+                placed.push(Placed {
+                    item,
+                    origin: Vector2::new(pen, baseline),
+                    line: index,
+                });
+                pen += item.advance;
+            }
+        }
+
+        for placed in &placed[first..] {
+            let item = &placed.item;
+            let right = placed.origin.x + item.advance;
+            let extent = &mut extents[item.cluster];
+            *extent = Some(match *extent {
+                Some(extent) => Extent {
+                    left: extent.left.min(placed.origin.x),
+                    right: extent.right.max(right),
+                    ..extent
+                },
+                None => Extent {
+                    left: placed.origin.x,
+                    right,
+                    line: index,
+                    baseline,
+                    rtl: item.level % 2 == 1,
+                },
+            });
+        }
+        lines.push(Line {
+            baseline,
+            metrics,
+        });
+        top -= metrics.height() * desc.line_spacing;
+    }
+
+    Layout {
+        carets: carets(&extents, items, start, lines.len() - 1),
+        placed,
+        lines,
+    }
+}
+
+// Places a caret before each char. The width of each cluster is split evenly between its chars,
+// starting from its leading edge, so each char of a ligature gets its own caret. The caret after
+// the last char sits on the trailing edge of the last cluster, or at the start of the last line if
+// the text ends in a line break.
+fn carets(extents: &[Option<Extent>], items: &[Item], start: Vector2<f32>, last_line: usize) -> Vec<Caret> {
+    let mut carets = Vec::with_capacity(extents.len() + 1);
+    let mut previous = Caret {
+        line: 0,
+        pos: start,
+    };
+    let mut index = 0;
+    while index < extents.len() {
+        let extent = match extents[index] {
+            Some(extent) => extent,
+            None => {
+                // Chars before the first cluster share the caret at the start of the text.
+                carets.push(previous);
+                index += 1;
+                continue;
+            }
+        };
+        // Extents are only kept for the first char of each cluster.
+        let mut end = index + 1;
+        while end < extents.len() && extents[end].is_none() {
+            end += 1;
+        }
+        let count = (end - index) as f32;
+        for char_index in 0..(end - index) {
+            let step = (extent.right - extent.left) * char_index as f32 / count;
+            let x = if extent.rtl {
+                extent.right - step
+            } else {
+                extent.left + step
+            };
+            carets.push(Caret {
+                line: extent.line,
+                pos: Vector2::new(x, extent.baseline),
+            });
+        }
+        previous = Caret {
+            line: extent.line,
+            pos: Vector2::new(
+                if extent.rtl {
+                    extent.left
+                } else {
+                    extent.right
+                },
+                extent.baseline,
+            ),
+        };
+        index = end;
+    }
+    match items.last() {
+        Some(item) if !item.newline => carets.push(previous),
+        _ => carets.push(Caret {
+            line: last_line,
+            pos: start,
+        }),
+    }
+    carets
+}
+
+// The tallest metrics of the items on a line.
+fn line_metrics(items: &[Item], empty: LineMetrics) -> LineMetrics {
+    if items.is_empty() {
+        return empty;
+    }
+    items.iter().fold(
+        LineMetrics {
+            ascent: 0.0,
+            descent: 0.0,
+            gap: 0.0,
+        },
+        |line, item| LineMetrics {
+            ascent: line.ascent.max(item.metrics.ascent),
+            descent: line.descent.min(item.metrics.descent),
+            gap: line.gap.max(item.metrics.gap),
+        },
+    )
+}
+
+// Breaks the items into lines, as ranges of items in logical order. A line ends after each line
+// break, and before the cluster that would take it past the max width. Clusters are never split.
+fn break_lines(desc: &Text, items: &[Item]) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut width = 0.0;
+    let mut index = 0;
+    while index < items.len() {
+        let mut end = index + 1;
+        while end < items.len() && items[end].cluster == items[index].cluster {
+            end += 1;
+        }
+        let item = &items[index];
+        let advance: f32 = items[index..end].iter().map(|item| item.advance).sum();
+        if item.newline {
+            lines.push(start..end);
+            start = end;
+            width = 0.0;
+            index = end;
+            continue;
+        }
+        match desc.max_width {
+            Some(max_width) if !item.whitespace && index > start && width + advance > max_width => {
+                // Words move to the next line whole, unless they fill a line on their own.
+                let split = match desc.wrap_style {
+                    WrapStyle::Word => items[start..index].iter().rposition(|item| item.whitespace),
+                    WrapStyle::Letter => None,
+                };
+                let split = split.map_or(index, |split| start + split + 1);
+                lines.push(start..split);
+                width = items[split..index].iter().map(|item| item.advance).sum();
+                start = split;
+            }
+            _ => {}
+        }
+        width += advance;
+        index = end;
+    }
+    lines.push(start..items.len());
+    lines
+}
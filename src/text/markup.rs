@@ -0,0 +1,154 @@
+use crate::colors::*;
+use crate::*;
+
+#[derive(Copy, Clone)]
+struct Style {
+    scale: Option<u32>,
+    color: Option<RGBA8>,
+    font: Option<FontToken>,
+}
+
+/// Parses markup into styled spans. Tags are [color=...], [size=...] and [font=...], closed with
+/// [/color], [/size] and [/font]. Tags can be nested, and [[ is an escaped [.
+pub fn parse(markup: &str, fonts: &[FontToken]) -> Result<Vec<TextSpan>, &'static str> {
+    let mut spans = Vec::new();
+    let mut stack: Vec<(&str, Style)> = Vec::new();
+    let mut style = Style {
+        scale: None,
+        color: None,
+        font: None,
+    };
+    let mut string = String::new();
+    let mut rest = markup;
+    while let Some(start) = rest.find('[') {
+        string.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        if rest.starts_with('[') {
+            string.push('[');
+            rest = &rest[1..];
+            continue;
+        }
+        let end = rest.find(']').ok_or("Unclosed markup tag.")?;
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+
+        // Each style change starts a new span.
+        if !string.is_empty() {
+            spans.push(span(&string, style));
+            string.clear();
+        }
+        if tag.starts_with('/') {
+            match stack.pop() {
+                Some((name, previous)) if name == &tag[1..] => style = previous,
+                _ => Err("Mismatched closing markup tag.")?,
+            }
+            continue;
+        }
+        let (name, value) = match tag.find('=') {
+            Some(split) => (&tag[..split], &tag[split + 1..]),
+            None => Err("Markup tag is missing a value.")?,
+        };
+        stack.push((name, style));
+        match name {
+            "color" => style.color = Some(parse_color(value)?),
+            "size" => style.scale = Some(value.parse().or(Err("Invalid markup size."))?),
+            "font" => {
+                let index: usize = value.parse().or(Err("Invalid markup font."))?;
+                style.font = Some(*fonts.get(index).ok_or("Markup font index is out of range.")?);
+            }
+            _ => Err("Unknown markup tag.")?,
+        }
+    }
+    string.push_str(rest);
+    if !stack.is_empty() {
+        Err("Markup tag was never closed.")?
+    }
+    if !string.is_empty() {
+        spans.push(span(&string, style));
+    }
+    Ok(spans)
+}
+
+fn span(string: &str, style: Style) -> TextSpan {
+    TextSpan {
+        string: String::from(string),
+        scale: style.scale,
+        color: style.color,
+        font: style.font,
+    }
+}
+
+fn parse_color(value: &str) -> Result<RGBA8, &'static str> {
+    let color = match value {
+        "red" => RED,
+        "purple" => PURPLE,
+        "blue" => BLUE,
+        "green" => GREEN,
+        "yellow" => YELLOW,
+        "orange" => ORANGE,
+        "magenta" => MAGENTA,
+        "white" => WHITE,
+        "black" => BLACK,
+        "transparent" => TRANSPARENT,
+        _ => {
+            if !value.starts_with('#') || !(value.len() == 7 || value.len() == 9) {
+                Err("Markup colors must be a name, #RRGGBB, or #RRGGBBAA.")?
+            }
+            let channel =
+                |index: usize| value.get(index..index + 2).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            let alpha = if value.len() == 9 {
+                channel(7)
+            } else {
+                Some(255)
+            };
+            match (channel(1), channel(3), channel(5), alpha) {
+                (Some(r), Some(g), Some(b), Some(a)) => RGBA8::new_raw(r, g, b, a),
+                _ => Err("Invalid markup color.")?,
+            }
+        }
+    };
+    Ok(color)
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn markup() {
+        let fonts = [FontToken::new(0), FontToken::new(3)];
+        let spans = parse("Talk to [color=red]Bob [size=32]now[/size][/color][[ok] [font=1]x[/font]", &fonts)
+            .unwrap();
+        assert_eq!(spans.len(), 5);
+        assert_eq!(spans[0].string, "Talk to ");
+        assert_eq!(spans[0].color, None);
+        assert_eq!(spans[1].string, "Bob ");
+        assert_eq!(spans[1].color, Some(RED));
+        assert_eq!(spans[2].string, "now");
+        assert_eq!(spans[2].color, Some(RED));
+        assert_eq!(spans[2].scale, Some(32));
+        assert_eq!(spans[3].string, "[ok] ");
+        assert_eq!(spans[3].color, None);
+        assert_eq!(spans[4].string, "x");
+        assert_eq!(spans[4].font, Some(FontToken::new(3)));
+    }
+
+    #[test]
+    fn markup_errors() {
+        assert_eq!(
+            parse("[color=#00ff0080]a[/color]", &[]).unwrap()[0].color,
+            Some(RGBA8::new_raw(0, 255, 0, 128))
+        );
+        assert!(parse("[color=red]a", &[]).is_err());
+        assert!(parse("[color=red]a[/size]", &[]).is_err());
+        assert!(parse("[color=nope]a[/color]", &[]).is_err());
+        assert!(parse("[font=0]a[/font]", &[]).is_err());
+        assert!(parse("[bold]a[/bold]", &[]).is_err());
+        assert!(parse("a[color=red", &[]).is_err());
+    }
+}
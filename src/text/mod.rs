@@ -1,3 +1,11 @@
+pub(crate) use bmfont::BitmapFont;
 pub(crate) use cache::TextCache;
 
+mod bmfont;
 mod cache;
+mod effect;
+mod layout;
+pub(crate) mod markup;
+mod sdf;
+mod shaping;
+mod shelf;
@@ -0,0 +1,87 @@
+/// Size in pixels glyphs are rasterized at before being converted into distance fields. Distance
+/// field glyphs are drawn at other sizes by scaling this.
+pub const SDF_SIZE: f32 = 48.0;
+/// Distance in pixels, at SDF_SIZE, the field extends past the edge of each glyph. This is also the
+/// padding added around each glyph.
+pub const SDF_SPREAD: u32 = 6;
+
+/// Converts a coverage bitmap into a signed distance field. The output is padded by SDF_SPREAD on
+/// each side. A value of 128 is on the edge of the glyph, with larger values inside and smaller
+/// values outside, reaching 0 and 255 at SDF_SPREAD pixels away from the edge.
+pub fn generate(bitmap: &[u8], width: u32, height: u32) -> (Vec<u8>, u32, u32) {
+    let spread = SDF_SPREAD as i32;
+    let out_width = width as i32 + spread * 2;
+    let out_height = height as i32 + spread * 2;
+    let inside = |x: i32, y: i32| {
+        let x = x - spread;
+        let y = y - spread;
+        x >= 0
+            && y >= 0
+            && x < width as i32
+            && y < height as i32
+            && bitmap[(y * width as i32 + x) as usize] >= 128
+    };
+
+    let mut output = Vec::with_capacity((out_width * out_height) as usize);
+    for y in 0..out_height {
+        for x in 0..out_width {
+            let state = inside(x, y);
+            // Searches the surrounding window for the closest pixel on the other side of the edge.
+            let mut closest = (spread * spread * 2) as f32;
+            for dy in -spread..=spread {
+                for dx in -spread..=spread {
+                    let distance = (dx * dx + dy * dy) as f32;
+                    if distance < closest && inside(x + dx, y + dy) != state {
+                        closest = distance;
+                    }
+                }
+            }
+            // The edge sits halfway between the two pixel centers.
+            let distance = (closest.sqrt() - 0.5).min(spread as f32);
+            let signed = if state {
+                distance
+            } else {
+                -distance
+            };
+            let value = 0.5 + signed / (spread as f32 * 2.0);
+            output.push((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+    }
+    (output, out_width as u32, out_height as u32)
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn distance_field() {
+        // A 4x4 square in the middle of an 8x8 bitmap.
+        let mut bitmap = vec![0u8; 64];
+        for y in 2..6 {
+            for x in 2..6 {
+                bitmap[y * 8 + x] = 255;
+            }
+        }
+        let (field, width, height) = generate(&bitmap, 8, 8);
+        let spread = SDF_SPREAD as usize;
+        assert_eq!(width, 8 + SDF_SPREAD * 2);
+        assert_eq!(height, 8 + SDF_SPREAD * 2);
+        let at = |x: usize, y: usize| field[(y + spread) * width as usize + x + spread];
+
+        // Pixels on either side of the edge straddle the midpoint.
+        assert!(at(2, 3) > 128);
+        assert!(at(1, 3) < 128);
+        assert_eq!(at(2, 3) - 128, 128 - at(1, 3) - 1);
+        // Values fall off with distance from the edge.
+        assert!(at(3, 3) > at(2, 3));
+        assert!(at(0, 3) < at(1, 3));
+        // The padding far from the glyph is fully outside.
+        assert_eq!(field[0], 0);
+    }
+}
@@ -0,0 +1,319 @@
+use unicode_bidi::BidiInfo;
+use unicode_normalization::char::{canonical_combining_class, compose};
+
+/// A character prepared for a font without shaping tables of its own, like a bitmap font.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShapedChar {
+    pub c: char,
+    /// Index of the char in the original text this came from. Ligatures keep the first.
+    pub source: usize,
+}
+
+/// Substitutes characters for fonts that can't shape text themselves, in logical order. Combining
+/// marks are composed into their base characters where possible, and Arabic letters are replaced
+/// with their contextual presentation forms.
+pub fn substitute(chars: &[ShapedChar]) -> Vec<ShapedChar> {
+    // Plain ASCII needs none of this.
+    if chars.iter().all(|ch| ch.c.is_ascii()) {
+        return chars.to_vec();
+    }
+    let chars = compose_marks(chars);
+    join_arabic(&chars)
+}
+
+// Composes combining marks into the preceding character when a precomposed form exists. Marks
+// aren't positioned without shaping tables, so precomposed forms are the only way they render.
+fn compose_marks(chars: &[ShapedChar]) -> Vec<ShapedChar> {
+    let mut output: Vec<ShapedChar> = Vec::with_capacity(chars.len());
+    for &ch in chars {
+        if canonical_combining_class(ch.c) != 0 {
+            if let Some(last) = output.last_mut() {
+                if let Some(composed) = compose(last.c, ch.c) {
+                    last.c = composed;
+                    continue;
+                }
+            }
+        }
+        output.push(ch);
+    }
+    output
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Arabic
+// ////////////////////////////////////////////////////////////////////////////
+
+#[derive(Copy, Clone, PartialEq)]
+enum Joining {
+    // Joins on both sides.
+    Dual,
+    // Joins only to the preceding letter.
+    Right,
+    // Joins on both sides without changing shape, like tatweel.
+    Causing,
+    // Skipped over when joining, like vowel marks.
+    Transparent,
+    None,
+}
+
+// Presentation forms of a letter: isolated, final, initial, medial. Right joining letters have no
+// initial or medial form.
+fn forms(c: char) -> Option<(u32, u32, u32, u32)> {
+    let c = c as u32;
+    let dual = |base: u32| Some((base, base + 1, base + 2, base + 3));
+    let right = |base: u32| Some((base, base + 1, 0, 0));
+    match c {
+        0x0621 => Some((0xFE80, 0, 0, 0)),
+        0x0622 => right(0xFE81),
+        0x0623 => right(0xFE83),
+        0x0624 => right(0xFE85),
+        0x0625 => right(0xFE87),
+        0x0626 => dual(0xFE89),
+        0x0627 => right(0xFE8D),
+        0x0628 => dual(0xFE8F),
+        0x0629 => right(0xFE93),
+        0x062A..=0x062E => dual(0xFE95 + (c - 0x062A) * 4),
+        0x062F..=0x0632 => right(0xFEA9 + (c - 0x062F) * 2),
+        0x0633..=0x063A => dual(0xFEB1 + (c - 0x0633) * 4),
+        0x0641..=0x0647 => dual(0xFED1 + (c - 0x0641) * 4),
+        0x0648 => right(0xFEED),
+        0x0649 => right(0xFEEF),
+        0x064A => dual(0xFEF1),
+        // Persian and Urdu letters.
+        0x067E => dual(0xFB56),
+        0x0686 => dual(0xFB7A),
+        0x0698 => right(0xFB8A),
+        0x06A9 => dual(0xFB8E),
+        0x06AF => dual(0xFB92),
+        0x06CC => dual(0xFBFC),
+        _ => None,
+    }
+}
+
+fn joining(c: char) -> Joining {
+    match c as u32 {
+        0x0640 | 0x200D => Joining::Causing,
+        0x064B..=0x065F | 0x0670 => Joining::Transparent,
+        _ => match forms(c) {
+            Some((_, _, 0, _)) => Joining::Right,
+            Some(_) => Joining::Dual,
+            None => Joining::None,
+        },
+    }
+}
+
+// Lam-alef ligatures: isolated and final forms, by the alef they're formed with.
+fn lam_alef(alef: char) -> Option<(u32, u32)> {
+    match alef as u32 {
+        0x0622 => Some((0xFEF5, 0xFEF6)),
+        0x0623 => Some((0xFEF7, 0xFEF8)),
+        0x0625 => Some((0xFEF9, 0xFEFA)),
+        0x0627 => Some((0xFEFB, 0xFEFC)),
+        _ => None,
+    }
+}
+
+// Replaces Arabic letters with the presentation form matching how they join to their neighbors.
+fn join_arabic(chars: &[ShapedChar]) -> Vec<ShapedChar> {
+    let kinds: Vec<Joining> = chars.iter().map(|ch| joining(ch.c)).collect();
+    // Finds the closest letter in the given direction, skipping transparent marks.
+    let neighbor = |index: usize, forward: bool| {
+        let mut i = index as isize;
+        loop {
+            i += if forward {
+                1
+            } else {
+                -1
+            };
+            if i < 0 || i as usize >= chars.len() {
+                return Joining::None;
+            }
+            if kinds[i as usize] != Joining::Transparent {
+                return kinds[i as usize];
+            }
+        }
+    };
+
+    let mut output = Vec::with_capacity(chars.len());
+    let mut index = 0;
+    while index < chars.len() {
+        let ch = chars[index];
+        let kind = kinds[index];
+        let previous = neighbor(index, false);
+        let joins_previous = (kind == Joining::Dual || kind == Joining::Right)
+            && (previous == Joining::Dual || previous == Joining::Causing);
+
+        // Lam followed directly by alef forms a ligature, which joins like a right joining letter.
+        if ch.c == '\u{0644}' && index + 1 < chars.len() {
+            if let Some((isolated, last)) = lam_alef(chars[index + 1].c) {
+                let form = if joins_previous {
+                    last
+                } else {
+                    isolated
+                };
+                output.push(ShapedChar {
+                    c: std::char::from_u32(form).unwrap(),
+                    ..ch
+                });
+                index += 2;
+                continue;
+            }
+        }
+
+        if let Some((isolated, last, initial, medial)) = forms(ch.c) {
+            let next = neighbor(index, true);
+            let joins_next = kind == Joining::Dual
+                && (next == Joining::Dual || next == Joining::Right || next == Joining::Causing);
+            let form = match (joins_previous, joins_next) {
+                (true, true) => medial,
+                (true, false) => last,
+                (false, true) => initial,
+                (false, false) => isolated,
+            };
+            output.push(ShapedChar {
+                c: std::char::from_u32(form).unwrap(),
+                ..ch
+            });
+        } else {
+            output.push(ch);
+        }
+        index += 1;
+    }
+    output
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Bidi
+// ////////////////////////////////////////////////////////////////////////////
+
+/// Returns the character drawn in place of the given one in right-to-left text.
+pub fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '\u{00AB}' => '\u{00BB}',
+        '\u{00BB}' => '\u{00AB}',
+        _ => c,
+    }
+}
+
+/// Resolves the bidirectional embedding level of each char in the text, along with the level of
+/// the paragraph it's in, per the Unicode bidirectional algorithm. Odd levels are right to left.
+/// Levels are resolved before lines are broken, so rule L1 is left to each line.
+pub fn levels(text: &str) -> Vec<(u8, u8)> {
+    let info = BidiInfo::new(text, None);
+    if !info.has_rtl() {
+        return vec![(0, 0); text.chars().count()];
+    }
+    let mut output = Vec::with_capacity(text.len());
+    for paragraph in &info.paragraphs {
+        let base = paragraph.level.number();
+        // Levels are per byte, and every char starts on its own byte.
+        for (offset, _) in text[paragraph.range.clone()].char_indices() {
+            output.push((info.levels[paragraph.range.start + offset].number(), base));
+        }
+    }
+    output
+}
+
+/// Returns the visual order of the items on a line from their levels, left to right, per rule L2:
+/// from the highest level down to the lowest odd level, every sequence of items at that level or
+/// higher is reversed.
+pub fn visual_order(levels: &[u8]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+    let highest = levels.iter().copied().max().unwrap_or(0);
+    let lowest_odd = levels.iter().copied().filter(|level| level % 2 == 1).min().unwrap_or(highest + 1);
+    let mut level = highest;
+    while level >= lowest_odd && level > 0 {
+        let mut index = 0;
+        while index < order.len() {
+            if levels[order[index]] >= level {
+                let start = index;
+                while index < order.len() && levels[order[index]] >= level {
+                    index += 1;
+                }
+                order[start..index].reverse();
+            } else {
+                index += 1;
+            }
+        }
+        level -= 1;
+    }
+    order
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+
+    fn chars(text: &str) -> Vec<ShapedChar> {
+        text.chars()
+            .enumerate()
+            .map(|(source, c)| ShapedChar {
+                c,
+                source,
+            })
+            .collect()
+    }
+
+    fn string(chars: &[ShapedChar]) -> String {
+        chars.iter().map(|ch| ch.c).collect()
+    }
+
+    #[test]
+    fn compose() {
+        let shaped = substitute(&chars("e\u{0301}a"));
+        assert_eq!(string(&shaped), "\u{00E9}a");
+        assert_eq!(shaped[1].source, 2);
+    }
+
+    #[test]
+    fn arabic() {
+        // Beh, beh, beh: initial, medial, final.
+        let shaped = substitute(&chars("\u{0628}\u{0628}\u{0628}"));
+        assert_eq!(string(&shaped), "\u{FE91}\u{FE92}\u{FE90}");
+        // Dal only joins to the letter before it, so the following beh is isolated.
+        let shaped = substitute(&chars("\u{0628}\u{062F}\u{0628}"));
+        assert_eq!(string(&shaped), "\u{FE91}\u{FEAA}\u{FE8F}");
+        // Lam alef becomes a single ligature.
+        let shaped = substitute(&chars("\u{0628}\u{0644}\u{0627}"));
+        assert_eq!(string(&shaped), "\u{FE91}\u{FEFC}");
+        assert_eq!(shaped[1].source, 1);
+        // Vowel marks don't break joining.
+        let shaped = substitute(&chars("\u{0628}\u{064E}\u{0628}"));
+        assert_eq!(string(&shaped), "\u{FE91}\u{064E}\u{FE90}");
+    }
+
+    #[test]
+    fn bidi_levels() {
+        // Hebrew letters alef, bet, gimel after Latin text.
+        let resolved = levels("ab \u{05D0}\u{05D1}\u{05D2} cd");
+        let embedding: Vec<u8> = resolved.iter().map(|&(level, _)| level).collect();
+        assert_eq!(embedding, [0, 0, 0, 1, 1, 1, 0, 0, 0]);
+        assert!(resolved.iter().all(|&(_, paragraph)| paragraph == 0));
+
+        // Each paragraph takes its direction from its first strong character.
+        let resolved = levels("\u{05D0}b\nab");
+        assert_eq!(resolved, [(1, 1), (2, 1), (1, 1), (0, 0), (0, 0)]);
+    }
+
+    #[test]
+    fn line_order() {
+        assert_eq!(visual_order(&[0, 0, 1, 1, 1, 0]), [0, 1, 4, 3, 2, 5]);
+        // Left to right text inside right to left text keeps its own order.
+        assert_eq!(visual_order(&[1, 1, 2, 2, 1]), [4, 2, 3, 1, 0]);
+        assert_eq!(visual_order(&[1, 2, 2, 1]), [3, 1, 2, 0]);
+        assert_eq!(visual_order(&[]), Vec::<usize>::new());
+    }
+}
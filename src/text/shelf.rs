@@ -0,0 +1,154 @@
+/// A region allocated from a shelf allocator.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Slot {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    // Free spans of the shelf as (x, width), sorted by x and never adjacent to each other.
+    free: Vec<(u32, u32)>,
+}
+
+/// Packs rectangles into horizontal shelves, each as tall as the first rectangle placed on it.
+/// Unlike the atlas packer, space can be freed and reused, which suits glyphs that come and go.
+pub struct ShelfAllocator {
+    width: u32,
+    height: u32,
+    shelves: Vec<Shelf>,
+}
+
+impl ShelfAllocator {
+    pub fn new(width: u32, height: u32) -> ShelfAllocator {
+        ShelfAllocator {
+            width,
+            height,
+            shelves: Vec::new(),
+        }
+    }
+
+    /// Allocates a region of the given size, or returns None if there's no room for it.
+    pub fn alloc(&mut self, width: u32, height: u32) -> Option<Slot> {
+        if width == 0 || height == 0 || width > self.width {
+            return None;
+        }
+        // Prefer the shelf wasting the least height.
+        let mut best: Option<(usize, usize)> = None;
+        for (shelf_index, shelf) in self.shelves.iter().enumerate() {
+            if shelf.height < height || shelf.height > height * 2 {
+                continue;
+            }
+            if let Some(span_index) = shelf.free.iter().position(|&(_, span)| span >= width) {
+                match best {
+                    Some((best_index, _)) if self.shelves[best_index].height <= shelf.height => {}
+                    _ => best = Some((shelf_index, span_index)),
+                }
+            }
+        }
+        if let Some((shelf_index, span_index)) = best {
+            let shelf = &mut self.shelves[shelf_index];
+            let (x, span) = shelf.free[span_index];
+            if span == width {
+                shelf.free.remove(span_index);
+            } else {
+                shelf.free[span_index] = (x + width, span - width);
+            }
+            return Some(Slot {
+                x,
+                y: shelf.y,
+                width,
+                height,
+            });
+        }
+
+        // Open a new shelf below the others.
+        let y = match self.shelves.last() {
+            Some(shelf) => shelf.y + shelf.height,
+            None => 0,
+        };
+        if y + height > self.height {
+            return None;
+        }
+        let mut free = Vec::new();
+        if width < self.width {
+            free.push((width, self.width - width));
+        }
+        self.shelves.push(Shelf {
+            y,
+            height,
+            free,
+        });
+        Some(Slot {
+            x: 0,
+            y,
+            width,
+            height,
+        })
+    }
+
+    /// Returns a region to the allocator. The slot must have been allocated by this allocator and
+    /// not freed since.
+    pub fn free(&mut self, slot: Slot) {
+        let shelf_index = match self.shelves.iter().position(|shelf| shelf.y == slot.y) {
+            Some(index) => index,
+            None => return,
+        };
+        let shelf = &mut self.shelves[shelf_index];
+        let index = shelf.free.iter().position(|&(x, _)| x > slot.x).unwrap_or(shelf.free.len());
+        shelf.free.insert(index, (slot.x, slot.width));
+        // Merge with the following span, then the preceding one.
+        if index + 1 < shelf.free.len() && slot.x + slot.width == shelf.free[index + 1].0 {
+            shelf.free[index].1 += shelf.free[index + 1].1;
+            shelf.free.remove(index + 1);
+        }
+        if index > 0 && shelf.free[index - 1].0 + shelf.free[index - 1].1 == slot.x {
+            shelf.free[index - 1].1 += shelf.free[index].1;
+            shelf.free.remove(index);
+        }
+        // Empty shelves at the bottom are removed so their height can be reused by any size.
+        while let Some(shelf) = self.shelves.last() {
+            if shelf.free.len() == 1 && shelf.free[0] == (0, self.width) {
+                self.shelves.pop();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn alloc_free() {
+        let mut shelves = ShelfAllocator::new(16, 16);
+        let a = shelves.alloc(8, 8).unwrap();
+        let b = shelves.alloc(8, 6).unwrap();
+        assert_eq!((a.x, a.y, b.x, b.y), (0, 0, 8, 0));
+        // The first shelf is full, so a second is opened below it.
+        let c = shelves.alloc(4, 8).unwrap();
+        assert_eq!((c.x, c.y), (0, 8));
+        assert_eq!(shelves.alloc(16, 1), None);
+
+        // Freed space is reused.
+        shelves.free(a);
+        assert_eq!(shelves.alloc(8, 8), Some(a));
+        shelves.free(a);
+        shelves.free(b);
+        assert_eq!(shelves.alloc(16, 5).map(|slot| (slot.x, slot.y)), Some((0, 0)));
+
+        // Freeing the bottom shelf returns its height.
+        shelves.free(c);
+        assert_eq!(shelves.alloc(16, 8).map(|slot| slot.y), Some(8));
+    }
+}
@@ -1,50 +1,277 @@
 use crate::colors::*;
-use crate::texture::packer::Packer;
+use crate::texture::packer::{Packer, Rect};
 use crate::texture::*;
 use cgmath::*;
 
 const MAX: u32 = 65536;
 const SIZE: u32 = 4096;
 pub(crate) const PIXEL_SIZE: u32 = MAX / SIZE;
+const DEFAULT_PADDING: u32 = 1;
+
+/// A modified region of the atlas to upload.
+pub struct AtlasUpdate {
+    pub x: u32,
+    pub y: u32,
+    pub image: Image,
+}
 
 pub struct TextureAtlas {
     packer: Packer,
     atlas: Image,
-    dirty: bool,
+    // The region of the atlas modified since it was last flushed.
+    dirty: Option<Rect>,
+    // Pixels of padding placed around each texture.
+    padding: u32,
+    // If the edge pixels of each texture are copied into its padding.
+    extrude: bool,
+    // Extruded textures and their padding, kept so updates can refresh the extrusion.
+    extruded: Vec<(Rect, u32)>,
+    // Pixels packed into the atlas so far, including padding.
+    packed: u64,
 }
 
 impl TextureAtlas {
     pub fn new() -> TextureAtlas {
+        TextureAtlas::with_size(SIZE)
+    }
+
+    fn with_size(size: u32) -> TextureAtlas {
         let mut atlas = TextureAtlas {
-            packer: Packer::new(SIZE, SIZE),
-            atlas: Image::from_color(WHITE, SIZE, SIZE),
-            dirty: false,
+            // The last row and column are left empty. A texture touching the far edge would end at
+            // a UV of 65536, which wraps to 0 in a u16.
+            packer: Packer::new(size - 1, size - 1),
+            atlas: Image::from_color(WHITE, size, size),
+            // The whole atlas is uploaded the first time it's synced.
+            dirty: Some(Rect::new(0, 0, size, size)),
+            padding: DEFAULT_PADDING,
+            extrude: true,
+            extruded: Vec::new(),
+            packed: 0,
         };
         atlas.add(Image::from_color(WHITE, 1, 1));
         atlas
     }
 
+    /// Sets the padding placed around textures added after this call. If extrude is true, the edge
+    /// pixels of each texture are copied into its padding so filtering at the edges doesn't sample
+    /// neighboring textures.
+    pub fn set_padding(&mut self, padding: u32, extrude: bool) {
+        self.padding = padding;
+        self.extrude = extrude;
+    }
+
     pub fn add(&mut self, texture: Image) -> Vector4<u16> {
-        if let Some(rect) = self.packer.pack(texture.width(), texture.height()) {
+        let padding = self.padding;
+        if let Some(rect) = self.packer.pack(texture.width() + padding * 2, texture.height() + padding * 2) {
+            self.mark_dirty(rect);
+            self.packed += rect.w as u64 * rect.h as u64;
+            let rect = Rect::new(rect.x + padding, rect.y + padding, texture.width(), texture.height());
             self.atlas.set_texture(rect.x, rect.y, &texture);
-            self.dirty = true;
-            Vector4::new(
-                (rect.x * PIXEL_SIZE) as u16,            // Left
-                ((rect.x + rect.w) * PIXEL_SIZE) as u16, // Right
-                (rect.y * PIXEL_SIZE) as u16,            // Top
-                ((rect.y + rect.h) * PIXEL_SIZE) as u16, // Bottom
-            )
+            if self.extrude && padding > 0 {
+                self.extrude(rect, padding);
+                self.extruded.push((rect, padding));
+            }
+            // The UVs only cover the texture, not its padding.
+            Self::uv(rect)
         } else {
             panic!("Unable to fit texture into atlas.");
         }
     }
 
-    pub fn sync(&mut self) -> Option<Image> {
-        if self.dirty {
-            self.dirty = false;
-            Some(self.atlas.clone())
-        } else {
-            None
+    /// Reserves a region of the given size in pixels, without padding, for the caller to manage
+    /// with set_pixels. Returns the position of its top left corner, or None if it doesn't fit.
+    pub fn reserve(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let rect = self.packer.pack(width, height)?;
+        self.packed += rect.w as u64 * rect.h as u64;
+        Some((rect.x, rect.y))
+    }
+
+    /// Returns the fraction of the atlas packed with textures, from 0.0 to 1.0. Space is never
+    /// given back, so this only grows.
+    pub fn occupancy(&self) -> f32 {
+        let size = self.atlas.width() as u64 * self.atlas.height() as u64;
+        (self.packed as f64 / size as f64) as f32
+    }
+
+    /// Writes the texture into the atlas with its top left corner at the given pixel position, and
+    /// returns its UVs. This is meant for regions claimed with reserve.
+    pub fn set_pixels(&mut self, x: u32, y: u32, texture: &Image) -> Vector4<u16> {
+        let rect = Rect::new(x, y, texture.width(), texture.height());
+        self.atlas.set_texture(x, y, texture);
+        self.mark_dirty(rect);
+        Self::uv(rect)
+    }
+
+    fn uv(rect: Rect) -> Vector4<u16> {
+        Vector4::new(
+            (rect.x * PIXEL_SIZE) as u16,            // Left
+            ((rect.x + rect.w) * PIXEL_SIZE) as u16, // Right
+            (rect.y * PIXEL_SIZE) as u16,            // Top
+            ((rect.y + rect.h) * PIXEL_SIZE) as u16, // Bottom
+        )
+    }
+
+    /// Overwrites the pixels in the given region of the atlas. The region is in the same UV layout
+    /// as returned by add, and the texture must be the same size as the region.
+    pub fn update(&mut self, uv: Vector4<u16>, texture: &Image) {
+        let x = std::cmp::min(uv.x, uv.y) as u32 / PIXEL_SIZE;
+        let y = std::cmp::min(uv.z, uv.w) as u32 / PIXEL_SIZE;
+        let region = Rect::new(x, y, texture.width(), texture.height());
+        self.atlas.set_texture(x, y, texture);
+        self.mark_dirty(region);
+        // Refresh the extrusion of the texture containing the region.
+        let extruded = self.extruded.iter().find(|(rect, _)| rect.union(&region) == *rect).copied();
+        if let Some((rect, padding)) = extruded {
+            self.extrude(rect, padding);
+            self.mark_dirty(Rect::new(
+                rect.x - padding,
+                rect.y - padding,
+                rect.w + padding * 2,
+                rect.h + padding * 2,
+            ));
+        }
+    }
+
+    /// Copies the edge pixels of the texture in the given rectangle outward into its padding.
+    fn extrude(&mut self, rect: Rect, padding: u32) {
+        let right = rect.x + rect.w - 1;
+        let bottom = rect.y + rect.h - 1;
+        for y in (rect.y - padding)..(bottom + padding + 1) {
+            let source_y = y.max(rect.y).min(bottom);
+            for x in (rect.x - padding)..(right + padding + 1) {
+                if x >= rect.x && x <= right && y >= rect.y && y <= bottom {
+                    continue;
+                }
+                let source_x = x.max(rect.x).min(right);
+                let pixel = self.atlas.get(source_x, source_y);
+                self.atlas.set(x, y, pixel);
+            }
         }
     }
+
+    fn mark_dirty(&mut self, rect: Rect) {
+        self.dirty = Some(match self.dirty {
+            Some(dirty) => dirty.union(&rect),
+            None => rect,
+        });
+    }
+
+    /// Returns a copy of the region of the atlas modified since the last flush, if any.
+    pub fn sync(&self) -> Option<AtlasUpdate> {
+        self.dirty.map(|rect| AtlasUpdate {
+            x: rect.x,
+            y: rect.y,
+            image: self.atlas.sub_image(rect.x, rect.y, rect.w, rect.h),
+        })
+    }
+
+    /// Marks the last synced region as handed off to the renderer.
+    pub fn flush(&mut self) {
+        self.dirty = None;
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+    use crate::Texture;
+
+    #[test]
+    fn padding_extrusion() {
+        let mut atlas = TextureAtlas::with_size(64);
+        atlas.set_padding(2, true);
+        let image = Image::from_vec(vec![RED, GREEN, BLUE, BLACK], 2, 2);
+        let uv = atlas.add(image);
+
+        // The UVs cover only the original pixels.
+        let x = uv.x as u32 / PIXEL_SIZE;
+        let y = uv.z as u32 / PIXEL_SIZE;
+        assert_eq!((uv.y - uv.x) as u32, 2 * PIXEL_SIZE);
+        assert_eq!((uv.w - uv.z) as u32, 2 * PIXEL_SIZE);
+        assert_eq!(atlas.atlas.get(x, y), RED);
+        assert_eq!(atlas.atlas.get(x + 1, y + 1), BLACK);
+
+        // Edges and corners are extruded into the padding.
+        assert_eq!(atlas.atlas.get(x - 2, y - 2), RED);
+        assert_eq!(atlas.atlas.get(x + 1, y - 1), GREEN);
+        assert_eq!(atlas.atlas.get(x - 1, y + 1), BLUE);
+        assert_eq!(atlas.atlas.get(x + 3, y + 3), BLACK);
+
+        // Updates refresh the extrusion.
+        atlas.update(uv, &Image::from_vec(vec![BLACK, BLACK, BLACK, RED], 2, 2));
+        assert_eq!(atlas.atlas.get(x - 2, y - 2), BLACK);
+        assert_eq!(atlas.atlas.get(x + 3, y + 3), RED);
+    }
+
+    #[test]
+    fn padding_packing() {
+        let mut atlas = TextureAtlas::with_size(64);
+        atlas.set_padding(1, false);
+        let a = atlas.add(Image::from_color(RED, 4, 4));
+        let b = atlas.add(Image::from_color(GREEN, 4, 4));
+        // Neighboring textures are separated by both of their paddings.
+        let gap = std::cmp::max(a.x, b.x) as i32 - std::cmp::min(a.y, b.y) as i32;
+        let gap_y = std::cmp::max(a.z, b.z) as i32 - std::cmp::min(a.w, b.w) as i32;
+        assert!(gap >= 2 * PIXEL_SIZE as i32 || gap_y >= 2 * PIXEL_SIZE as i32);
+    }
+
+    #[test]
+    fn occupancy() {
+        let mut atlas = TextureAtlas::with_size(64);
+        // The white pixel added with every atlas, and its padding.
+        assert_eq!(atlas.occupancy(), 9.0 / 4096.0);
+        atlas.set_padding(0, false);
+        atlas.add(Image::from_color(RED, 32, 32));
+        atlas.reserve(16, 16);
+        assert_eq!(atlas.occupancy(), (9.0 + 1024.0 + 256.0) / 4096.0);
+    }
+
+    #[test]
+    fn far_edge() {
+        let mut atlas = TextureAtlas::new();
+        atlas.set_padding(0, false);
+        // Nothing is packed against the far edge.
+        assert_eq!(atlas.reserve(SIZE, 8), None);
+        // Fills the rest of the first row, next to the white pixel and its padding.
+        let uv = atlas.add(Image::from_color(RED, SIZE - 4, 8));
+        assert_eq!(uv.y as u32, (SIZE - 1) * PIXEL_SIZE);
+        assert_eq!(Texture(uv).width() as u32, SIZE - 4);
+        assert_eq!(Texture(uv).height(), 8);
+    }
+
+    #[test]
+    fn update_dirty_regions() {
+        let mut atlas = TextureAtlas::with_size(64);
+        atlas.set_padding(0, false);
+        let uv = atlas.add(Image::from_color(RED, 8, 8));
+        let x = uv.x as u32 / PIXEL_SIZE;
+        let y = uv.z as u32 / PIXEL_SIZE;
+        atlas.flush();
+        assert!(atlas.sync().is_none());
+
+        // Only the updated region is written.
+        let region = Texture(uv).sub_texture(1, 2, 2, 1).unwrap();
+        atlas.update(region.0, &Image::from_vec(vec![GREEN, BLUE], 2, 1));
+        assert_eq!(atlas.atlas.get(x, y + 2), RED);
+        assert_eq!(atlas.atlas.get(x + 1, y + 2), GREEN);
+        assert_eq!(atlas.atlas.get(x + 2, y + 2), BLUE);
+        assert_eq!(atlas.atlas.get(x + 3, y + 2), RED);
+        let update = atlas.sync().unwrap();
+        assert_eq!((update.x, update.y), (x + 1, y + 2));
+        assert_eq!((update.image.width(), update.image.height()), (2, 1));
+
+        // Regions updated before the next flush are merged into one covering both.
+        let region = Texture(uv).sub_texture(5, 6, 1, 1).unwrap();
+        atlas.update(region.0, &Image::from_color(BLACK, 1, 1));
+        let update = atlas.sync().unwrap();
+        assert_eq!((update.x, update.y), (x + 1, y + 2));
+        assert_eq!((update.image.width(), update.image.height()), (5, 5));
+        assert_eq!(update.image.get(0, 0), GREEN);
+        assert_eq!(update.image.get(4, 4), BLACK);
+    }
 }
@@ -0,0 +1,148 @@
+use crate::texture::image::Image;
+use crate::RGBA8;
+use std::io::Read;
+
+const FILE_HEADER_SIZE: usize = 14;
+
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+#[inline(always)]
+fn read_u16(input: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([input[offset], input[offset + 1]])
+}
+
+#[inline(always)]
+fn read_u32(input: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([input[offset], input[offset + 1], input[offset + 2], input[offset + 3]])
+}
+
+/// Extracts the channel described by the mask from the value, scaled to 8 bits. Returns None if the
+/// mask is empty.
+fn channel(value: u32, mask: u32) -> Option<u8> {
+    if mask == 0 {
+        return None;
+    }
+    let shift = mask.trailing_zeros();
+    let bits = (mask >> shift).count_ones();
+    let raw = (value & mask) >> shift;
+    let max = (1u64 << bits) - 1;
+    Some(((raw as u64 * 255 + max / 2) / max) as u8)
+}
+
+pub fn read<R: Read>(mut bytes: R) -> Image {
+    let mut input = Vec::new();
+    bytes.read_to_end(&mut input).expect("Unable to read BMP payload.");
+    if input.len() < FILE_HEADER_SIZE + 12 || &input[0..2] != b"BM" {
+        panic!("Unable to read BMP info.");
+    }
+
+    let data_offset = read_u32(&input, 10) as usize;
+    let info = FILE_HEADER_SIZE;
+    let info_size = read_u32(&input, info) as usize;
+    let (width, height, depth, compression, colors_used) = if info_size == 12 {
+        // BITMAPCOREHEADER
+        let width = read_u16(&input, info + 4) as i32;
+        let height = read_u16(&input, info + 6) as i16 as i32;
+        let depth = read_u16(&input, info + 10);
+        (width, height, depth, BI_RGB, 0)
+    } else {
+        // BITMAPINFOHEADER and its V2 through V5 extensions.
+        let width = read_u32(&input, info + 4) as i32;
+        let height = read_u32(&input, info + 8) as i32;
+        let depth = read_u16(&input, info + 14);
+        let compression = read_u32(&input, info + 16);
+        let colors_used = read_u32(&input, info + 32) as usize;
+        (width, height, depth, compression, colors_used)
+    };
+
+    // A negative height means the rows are stored top to bottom.
+    let top_down = height < 0;
+    let width = width.unsigned_abs();
+    let height = height.unsigned_abs();
+
+    // Channel masks. Bitfield masks either live in the extended header or directly after the
+    // basic header.
+    let (mut r_mask, mut g_mask, mut b_mask, mut a_mask) = match depth {
+        16 => (0x7c00, 0x03e0, 0x001f, 0),
+        _ => (0x00ff_0000, 0x0000_ff00, 0x0000_00ff, 0),
+    };
+    if compression == BI_BITFIELDS || compression == BI_ALPHABITFIELDS {
+        let masks = info + 40;
+        r_mask = read_u32(&input, masks);
+        g_mask = read_u32(&input, masks + 4);
+        b_mask = read_u32(&input, masks + 8);
+        if compression == BI_ALPHABITFIELDS || info_size >= 56 {
+            a_mask = read_u32(&input, masks + 12);
+        }
+    } else if compression != BI_RGB {
+        panic!("BMP compression type {} is unsupported.", compression);
+    } else if depth == 32 && info_size >= 56 {
+        a_mask = read_u32(&input, info + 52);
+    }
+
+    // Palette, used by 1, 4, and 8 bit images.
+    let mut palette = Vec::new();
+    if depth <= 8 {
+        let entry_size = if info_size == 12 {
+            3
+        } else {
+            4
+        };
+        let count = if colors_used == 0 {
+            1 << depth
+        } else {
+            colors_used
+        };
+        let start = info + info_size;
+        for i in 0..count {
+            let offset = start + i * entry_size;
+            palette.push(RGBA8::new_raw(input[offset + 2], input[offset + 1], input[offset], 255));
+        }
+    }
+
+    // Rows are padded to 4 bytes.
+    let stride = (width as usize * depth as usize).div_ceil(32) * 4;
+    if input.len() < data_offset + stride * height as usize {
+        panic!("Unable to read BMP payload.");
+    }
+
+    let mut output = Vec::with_capacity((width * height) as usize);
+    for y in 0..height as usize {
+        let row = if top_down {
+            y
+        } else {
+            height as usize - y - 1
+        };
+        let line = &input[data_offset + row * stride..data_offset + (row + 1) * stride];
+        for x in 0..width as usize {
+            let pixel = match depth {
+                1 | 2 | 4 | 8 => {
+                    let bit = x * depth as usize;
+                    let shift = 8 - depth as usize - (bit % 8);
+                    let index = (line[bit / 8] >> shift) & ((1u16 << depth) - 1) as u8;
+                    palette[index as usize]
+                }
+                16 | 24 | 32 => {
+                    let offset = x * (depth as usize / 8);
+                    let value = match depth {
+                        16 => read_u16(line, offset) as u32,
+                        24 => u32::from_le_bytes([line[offset], line[offset + 1], line[offset + 2], 0]),
+                        _ => read_u32(line, offset),
+                    };
+                    RGBA8::new_raw(
+                        channel(value, r_mask).unwrap_or(0),
+                        channel(value, g_mask).unwrap_or(0),
+                        channel(value, b_mask).unwrap_or(0),
+                        channel(value, a_mask).unwrap_or(255),
+                    )
+                }
+                _ => panic!("BMP bit depth {} is unsupported.", depth),
+            };
+            output.push(pixel);
+        }
+    }
+
+    Image::from_vec(output, width, height)
+}
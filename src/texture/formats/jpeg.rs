@@ -0,0 +1,41 @@
+use crate::texture::image::Image;
+use crate::RGBA8;
+use jpeg_decoder::{Decoder, PixelFormat};
+use std::io::Read;
+
+pub fn read<R: Read>(bytes: R) -> Image {
+    let mut decoder = Decoder::new(bytes);
+    let input = decoder.decode().expect("Unable to read JPEG payload.");
+    let info = decoder.info().expect("Unable to read JPEG info.");
+    let width = info.width as u32;
+    let height = info.height as u32;
+
+    match info.pixel_format {
+        PixelFormat::RGB24 => {
+            let mut output = Vec::with_capacity(input.len() / 3);
+            for rgb in input.chunks_exact(3) {
+                output.push(RGBA8::new_raw(rgb[0], rgb[1], rgb[2], 255));
+            }
+            Image::from_vec(output, width, height)
+        }
+        PixelFormat::L8 => {
+            let mut output = Vec::with_capacity(input.len());
+            for g in input {
+                output.push(RGBA8::new_raw(g, g, g, 255));
+            }
+            Image::from_vec(output, width, height)
+        }
+        PixelFormat::CMYK32 => {
+            // The decoder outputs inverted CMYK, as written by Adobe applications.
+            let mut output = Vec::with_capacity(input.len() / 4);
+            for cmyk in input.chunks_exact(4) {
+                let k = cmyk[3] as u32;
+                let r = (cmyk[0] as u32 * k / 255) as u8;
+                let g = (cmyk[1] as u32 * k / 255) as u8;
+                let b = (cmyk[2] as u32 * k / 255) as u8;
+                output.push(RGBA8::new_raw(r, g, b, 255));
+            }
+            Image::from_vec(output, width, height)
+        }
+    }
+}
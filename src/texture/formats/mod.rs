@@ -1 +1,139 @@
+pub mod bmp;
+pub mod jpeg;
 pub mod png;
+pub mod qoi;
+pub mod tga;
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use crate::colors::*;
+    use crate::texture::Image;
+    use crate::{TextureFormat, RGBA8};
+
+    fn assert_pixels(image: &Image, width: u32, height: u32, expected: &[RGBA8]) {
+        assert_eq!(image.width(), width);
+        assert_eq!(image.height(), height);
+        assert_eq!(image.as_slice(), expected);
+    }
+
+    #[test]
+    fn detect() {
+        assert_eq!(
+            TextureFormat::detect(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n']),
+            Some(TextureFormat::PNG)
+        );
+        assert_eq!(TextureFormat::detect(&[0xff, 0xd8, 0xff, 0xe0]), Some(TextureFormat::JPEG));
+        assert_eq!(TextureFormat::detect(b"qoif\0\0\0\x01"), Some(TextureFormat::QOI));
+        assert_eq!(TextureFormat::detect(b"BM\0\0"), Some(TextureFormat::BMP));
+        let mut tga = vec![0u8; 18];
+        tga[2] = 2;
+        tga[16] = 32;
+        assert_eq!(TextureFormat::detect(&tga), Some(TextureFormat::TGA));
+        assert_eq!(TextureFormat::detect(b"not an image"), None);
+    }
+
+    #[test]
+    fn qoi() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"qoif");
+        bytes.extend_from_slice(&2u32.to_be_bytes());
+        bytes.extend_from_slice(&2u32.to_be_bytes());
+        bytes.extend_from_slice(&[4, 0]);
+        bytes.extend_from_slice(&[0xfe, 255, 0, 0]); // RGB red
+        bytes.extend_from_slice(&[0xc0]); // Run of 1
+        bytes.extend_from_slice(&[0xff, 0, 0, 255, 128]); // RGBA
+        bytes.extend_from_slice(&[0x40 | (2 << 4) | (3 << 2) | 2]); // Diff, green + 1
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        let image = super::qoi::read(bytes.as_slice());
+        assert_pixels(
+            &image,
+            2,
+            2,
+            &[RED, RED, RGBA8::new_raw(0, 0, 255, 128), RGBA8::new_raw(0, 1, 255, 128)],
+        );
+    }
+
+    #[test]
+    fn tga() {
+        // Uncompressed true color, bottom left origin.
+        let mut bytes = vec![0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 2, 0, 24, 0];
+        bytes.extend_from_slice(&[255, 0, 0]); // Bottom row, BGR blue
+        bytes.extend_from_slice(&[0, 0, 255]); // Top row, BGR red
+        let image = super::tga::read(bytes.as_slice());
+        assert_pixels(&image, 1, 2, &[RED, BLUE]);
+
+        // Run length encoded true color, top left origin.
+        let mut bytes = vec![0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 1, 0, 32, 0x28];
+        bytes.extend_from_slice(&[0x81, 0, 255, 0, 255]); // Run of 2 green
+        bytes.extend_from_slice(&[0x00, 0, 0, 0, 0]); // Raw packet of 1 transparent
+        let image = super::tga::read(bytes.as_slice());
+        assert_pixels(&image, 3, 1, &[GREEN, GREEN, TRANSPARENT]);
+    }
+
+    #[test]
+    fn jpeg() {
+        // Baseline grayscale 8x8, a single block with only a DC coefficient.
+        let mut bytes = vec![0xff, 0xd8];
+        bytes.extend_from_slice(&[0xff, 0xdb, 0, 67, 0x00]); // Quantization table of all 1
+        bytes.extend_from_slice(&[1; 64]);
+        bytes.extend_from_slice(&[0xff, 0xc0, 0, 11, 8, 0, 8, 0, 8, 1, 1, 0x11, 0]); // Frame, 8x8, 1 component
+        bytes.extend_from_slice(&[
+            0xff, 0xc4, 0, 20, 0x00, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 10,
+        ]);
+        bytes
+            .extend_from_slice(&[0xff, 0xc4, 0, 20, 0x10, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&[0xff, 0xda, 0, 8, 1, 1, 0x00, 0, 63, 0]); // Scan
+        bytes.extend_from_slice(&[0x48, 0x0f]); // DC of 576 (8 * (200 - 128)), end of block
+        bytes.extend_from_slice(&[0xff, 0xd9]);
+        let image = super::jpeg::read(bytes.as_slice());
+        assert_pixels(&image, 8, 8, &[RGBA8::new_raw(200, 200, 200, 255); 64]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unable to read QOI payload.")]
+    fn qoi_dimensions_exceed_payload() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"qoif");
+        bytes.extend_from_slice(&u32::MAX.to_be_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_be_bytes());
+        bytes.extend_from_slice(&[4, 0]);
+        bytes.extend_from_slice(&[0xfd]); // Run of 62
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        super::qoi::read(bytes.as_slice());
+    }
+
+    #[test]
+    #[should_panic(expected = "TGA color map index is out of range.")]
+    fn tga_color_map_index() {
+        // Color mapped with a 24 bit palette of one entry starting at index 1, but the pixel is index 0.
+        let mut bytes = vec![0, 1, 1, 1, 0, 1, 0, 24, 0, 0, 0, 0, 1, 0, 1, 0, 8, 0x20];
+        bytes.extend_from_slice(&[0, 0, 255]);
+        bytes.extend_from_slice(&[0]);
+        super::tga::read(bytes.as_slice());
+    }
+
+    #[test]
+    fn bmp() {
+        // 24 bit, 2x2, bottom up with 2 bytes of row padding.
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"BM");
+        bytes.extend_from_slice(&70u32.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        bytes.extend_from_slice(&54u32.to_le_bytes());
+        bytes.extend_from_slice(&40u32.to_le_bytes());
+        bytes.extend_from_slice(&2i32.to_le_bytes());
+        bytes.extend_from_slice(&2i32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&24u16.to_le_bytes());
+        bytes.extend_from_slice(&[0; 24]);
+        bytes.extend_from_slice(&[255, 0, 0, 0, 255, 0, 0, 0]); // Bottom row, blue green
+        bytes.extend_from_slice(&[0, 0, 255, 255, 255, 255, 0, 0]); // Top row, red white
+        let image = super::bmp::read(bytes.as_slice());
+        assert_pixels(&image, 2, 2, &[RED, WHITE, BLUE, GREEN]);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::texture::image::Image;
+use crate::RGBA8;
+use std::io::Read;
+
+const MAGIC: &[u8; 4] = b"qoif";
+const HEADER_SIZE: usize = 14;
+const END_MARKER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+const OP_INDEX: u8 = 0x00;
+const OP_DIFF: u8 = 0x40;
+const OP_LUMA: u8 = 0x80;
+const OP_RUN: u8 = 0xc0;
+const OP_RGB: u8 = 0xfe;
+const OP_RGBA: u8 = 0xff;
+const MASK_2: u8 = 0xc0;
+/// The longest run a single OP_RUN byte can encode.
+const MAX_RUN: usize = 62;
+
+#[inline(always)]
+fn hash(px: RGBA8) -> usize {
+    (px.r as usize * 3 + px.g as usize * 5 + px.b as usize * 7 + px.a as usize * 11) % 64
+}
+
+pub fn read<R: Read>(mut bytes: R) -> Image {
+    let mut input = Vec::new();
+    bytes.read_to_end(&mut input).expect("Unable to read QOI payload.");
+    if input.len() < HEADER_SIZE + END_MARKER.len() || &input[0..4] != MAGIC {
+        panic!("Unable to read QOI info.");
+    }
+    let width = u32::from_be_bytes([input[4], input[5], input[6], input[7]]);
+    let height = u32::from_be_bytes([input[8], input[9], input[10], input[11]]);
+    // Channels (input[12]) and colorspace (input[13]) are informative only, the stream always
+    // decodes to RGBA.
+
+    // No byte of the stream decodes to more than a full run of pixels, so larger dimensions can't be
+    // backed by the payload. Rejecting them here keeps a corrupt header from reserving a huge buffer.
+    let end = input.len() - END_MARKER.len();
+    let len = match (width as usize).checked_mul(height as usize) {
+        Some(len) if len <= (end - HEADER_SIZE) * MAX_RUN => len,
+        _ => panic!("Unable to read QOI payload."),
+    };
+    let mut output = Vec::with_capacity(len);
+    let mut index = [RGBA8::new_raw(0, 0, 0, 0); 64];
+    let mut px = RGBA8::new_raw(0, 0, 0, 255);
+    let mut p = HEADER_SIZE;
+
+    while output.len() < len {
+        if p >= end {
+            panic!("Unable to read QOI payload.");
+        }
+        let b1 = input[p];
+        p += 1;
+        if b1 == OP_RGB {
+            px.r = input[p];
+            px.g = input[p + 1];
+            px.b = input[p + 2];
+            p += 3;
+        } else if b1 == OP_RGBA {
+            px.r = input[p];
+            px.g = input[p + 1];
+            px.b = input[p + 2];
+            px.a = input[p + 3];
+            p += 4;
+        } else {
+            match b1 & MASK_2 {
+                OP_INDEX => {
+                    px = index[b1 as usize];
+                }
+                OP_DIFF => {
+                    px.r = px.r.wrapping_add(((b1 >> 4) & 0x03).wrapping_sub(2));
+                    px.g = px.g.wrapping_add(((b1 >> 2) & 0x03).wrapping_sub(2));
+                    px.b = px.b.wrapping_add((b1 & 0x03).wrapping_sub(2));
+                }
+                OP_LUMA => {
+                    let b2 = input[p];
+                    p += 1;
+                    let vg = (b1 & 0x3f).wrapping_sub(32);
+                    px.r = px.r.wrapping_add(vg.wrapping_sub(8).wrapping_add((b2 >> 4) & 0x0f));
+                    px.g = px.g.wrapping_add(vg);
+                    px.b = px.b.wrapping_add(vg.wrapping_sub(8).wrapping_add(b2 & 0x0f));
+                }
+                OP_RUN => {
+                    // The run length is stored with a bias of -1.
+                    let run = ((b1 & 0x3f) as usize + 1).min(len - output.len());
+                    for _ in 1..run {
+                        output.push(px);
+                    }
+                }
+                _ => unreachable!(),
+            }
+        }
+        index[hash(px)] = px;
+        output.push(px);
+    }
+
+    Image::from_vec(output, width, height)
+}
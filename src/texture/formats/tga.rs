@@ -0,0 +1,147 @@
+use crate::texture::image::Image;
+use crate::RGBA8;
+use std::io::Read;
+
+const HEADER_SIZE: usize = 18;
+
+#[inline(always)]
+fn read_u16(input: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([input[offset], input[offset + 1]])
+}
+
+/// Reads a single pixel in the given bit depth. TGA stores color channels in BGR(A) order.
+fn read_pixel(input: &[u8], depth: u8, grayscale: bool) -> Result<RGBA8, &'static str> {
+    Ok(match (depth, grayscale) {
+        (8, true) => RGBA8::new_raw(input[0], input[0], input[0], 255),
+        (16, true) => RGBA8::new_raw(input[0], input[0], input[0], input[1]),
+        (15, false) | (16, false) => {
+            let v = read_u16(input, 0);
+            let r = ((v >> 10) & 0x1f) as u8;
+            let g = ((v >> 5) & 0x1f) as u8;
+            let b = (v & 0x1f) as u8;
+            // Expand 5 bits to 8 bits by replicating the high bits into the low bits.
+            RGBA8::new_raw((r << 3) | (r >> 2), (g << 3) | (g >> 2), (b << 3) | (b >> 2), 255)
+        }
+        (24, false) => RGBA8::new_raw(input[2], input[1], input[0], 255),
+        (32, false) => RGBA8::new_raw(input[2], input[1], input[0], input[3]),
+        _ => Err("TGA pixel depth is unsupported.")?,
+    })
+}
+
+/// Returns the len bytes starting at offset, or an error if the payload is too short.
+#[inline(always)]
+fn read_slice(input: &[u8], offset: usize, len: usize) -> Result<&[u8], &'static str> {
+    offset.checked_add(len).and_then(|end| input.get(offset..end)).ok_or("Unable to read TGA payload.")
+}
+
+pub fn read<R: Read>(mut bytes: R) -> Image {
+    let mut input = Vec::new();
+    bytes.read_to_end(&mut input).expect("Unable to read TGA payload.");
+    match decode(&input) {
+        Ok(image) => image,
+        Err(message) => panic!("{}", message),
+    }
+}
+
+fn decode(input: &[u8]) -> Result<Image, &'static str> {
+    if input.len() < HEADER_SIZE {
+        Err("Unable to read TGA info.")?;
+    }
+
+    let id_length = input[0] as usize;
+    let color_map_type = input[1];
+    let image_type = input[2];
+    let color_map_start = read_u16(input, 3) as usize;
+    let color_map_length = read_u16(input, 5) as usize;
+    let color_map_depth = input[7];
+    let width = read_u16(input, 12) as u32;
+    let height = read_u16(input, 14) as u32;
+    let depth = input[16];
+    let descriptor = input[17];
+
+    let rle = image_type & 0x08 != 0;
+    let mapped = image_type & 0x07 == 1;
+    let grayscale = image_type & 0x07 == 3;
+    if image_type & 0x07 == 0 || image_type & 0x07 > 3 {
+        Err("TGA image type is unsupported.")?;
+    }
+
+    let mut p = HEADER_SIZE + id_length;
+
+    // Color map, only used by color mapped images but may be present in any image.
+    let mut palette = Vec::new();
+    if color_map_type == 1 {
+        let entry_size = (color_map_depth as usize).div_ceil(8);
+        for i in 0..color_map_length {
+            let entry = read_slice(input, p + i * entry_size, entry_size)?;
+            palette.push(read_pixel(entry, color_map_depth, false)?);
+        }
+        p += color_map_length * entry_size;
+    }
+
+    if ![8, 15, 16, 24, 32].contains(&depth) {
+        Err("TGA pixel depth is unsupported.")?;
+    }
+    let pixel_size = (depth as usize).div_ceil(8);
+    let pixel = |data: &[u8]| -> Result<RGBA8, &'static str> {
+        if mapped {
+            let index = match pixel_size {
+                1 => data[0] as usize,
+                _ => read_u16(data, 0) as usize,
+            };
+            index
+                .checked_sub(color_map_start)
+                .and_then(|i| palette.get(i))
+                .copied()
+                .ok_or("TGA color map index is out of range.")
+        } else {
+            read_pixel(data, depth, grayscale)
+        }
+    };
+
+    // Width and height are 16 bits each, so this can't overflow.
+    let len = width as usize * height as usize;
+    let mut output = Vec::with_capacity(len);
+    if rle {
+        while output.len() < len {
+            let header = read_slice(input, p, 1)?[0];
+            p += 1;
+            let count = ((header & 0x7f) as usize + 1).min(len - output.len());
+            if header & 0x80 != 0 {
+                let color = pixel(read_slice(input, p, pixel_size)?)?;
+                p += pixel_size;
+                for _ in 0..count {
+                    output.push(color);
+                }
+            } else {
+                for _ in 0..count {
+                    output.push(pixel(read_slice(input, p, pixel_size)?)?);
+                    p += pixel_size;
+                }
+            }
+        }
+    } else {
+        for data in read_slice(input, p, len * pixel_size)?.chunks_exact(pixel_size) {
+            output.push(pixel(data)?);
+        }
+    }
+
+    // Images are stored top to bottom, so flip the rows of bottom origin images (the default). Right
+    // origin images are flipped horizontally.
+    let right_origin = descriptor & 0x10 != 0;
+    let top_origin = descriptor & 0x20 != 0;
+    let row = width as usize;
+    if !top_origin {
+        for y in 0..(height as usize / 2) {
+            let (top, bottom) = output.split_at_mut((height as usize - y - 1) * row);
+            top[y * row..(y + 1) * row].swap_with_slice(&mut bottom[..row]);
+        }
+    }
+    if right_origin {
+        for line in output.chunks_exact_mut(row) {
+            line.reverse();
+        }
+    }
+
+    Ok(Image::from_vec(output, width, height))
+}
@@ -13,6 +13,10 @@ impl Image {
     pub fn from_raw<R: Read>(bytes: R, format: TextureFormat) -> Image {
         match format {
             TextureFormat::PNG => formats::png::read(bytes),
+            TextureFormat::BMP => formats::bmp::read(bytes),
+            TextureFormat::TGA => formats::tga::read(bytes),
+            TextureFormat::QOI => formats::qoi::read(bytes),
+            TextureFormat::JPEG => formats::jpeg::read(bytes),
         }
     }
 
@@ -65,6 +69,16 @@ impl Image {
         self.pixels[index] = val;
     }
 
+    /// Copies a region of this image into a new image.
+    pub fn sub_image(&self, offset_x: u32, offset_y: u32, width: u32, height: u32) -> Image {
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for y in offset_y..(offset_y + height) {
+            let start = self.index_for(offset_x, y);
+            pixels.extend_from_slice(&self.pixels[start..start + width as usize]);
+        }
+        Image::from_vec(pixels, width, height)
+    }
+
     pub fn set_texture(&mut self, offset_x: u32, offset_y: u32, tex: &Image) {
         for x in 0..tex.width {
             for y in 0..tex.height {
@@ -1,3 +1,4 @@
+pub(crate) use atlas::AtlasUpdate;
 pub(crate) use atlas::TextureAtlas;
 pub(crate) use atlas::PIXEL_SIZE;
 pub(crate) use image::Image;
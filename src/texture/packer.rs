@@ -1,4 +1,4 @@
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Rect {
     pub x: u32,
     pub y: u32,
@@ -36,6 +36,15 @@ impl Rect {
         self.x + self.w - 1
     }
 
+    /// Returns the smallest rectangle containing both rectangles.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.left().min(other.left());
+        let y = self.top().min(other.top());
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        Rect::new(x, y, right - x + 1, bottom - y + 1)
+    }
+
     #[inline(always)]
     fn contains(&self, other: &Rect) -> bool {
         self.left() <= other.left()
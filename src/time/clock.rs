@@ -1,39 +1,34 @@
 use crate::time::convert::*;
-use std::thread::sleep;
+use crate::time::source::*;
 use std::time::Duration;
-use std::time::Instant;
 
-pub struct Clock {
-    last_tick: Instant,
+pub struct Clock<S: TimeSource = RealTime> {
+    source: S,
+    last_tick: Duration,
     target: u64,
     delta: f32,
 }
 
-impl Clock {
-    pub fn new(tps: u64) -> Clock {
-        Clock {
-            last_tick: Instant::now(),
-            target: Clock::tps_to_target(tps),
-            delta: 0f32,
-        }
+impl Clock<RealTime> {
+    pub fn new(tps: u64) -> Clock<RealTime> {
+        Clock::with_source(tps, RealTime::new())
     }
+}
 
-    fn tps_to_target(tps: u64) -> u64 {
-        if tps == 0 {
-            0
-        } else {
-            NANOS_PER_SEC / tps
+impl<S: TimeSource> Clock<S> {
+    /// Creates a clock that measures time from the given source.
+    pub fn with_source(tps: u64, source: S) -> Clock<S> {
+        Clock {
+            last_tick: source.now(),
+            source,
+            target: tps_to_target(tps),
+            delta: 0f32,
         }
     }
 
-    #[inline]
-    fn duration_to_delta(duration: &Duration) -> f32 {
-        (as_nanoseconds(duration) as f64 / NANOS_PER_SEC as f64) as f32
-    }
-
     /// Sets the target ticks per second for the clock.
     pub fn set_tps(&mut self, tps: u64) {
-        self.target = Clock::tps_to_target(tps);
+        self.target = tps_to_target(tps);
     }
 
     #[inline]
@@ -50,22 +45,49 @@ impl Clock {
     /// sleep for 6ms.
     pub fn tick(&mut self) {
         // Find the duration since ticked last.
-        let duration = as_nanoseconds(&self.last_tick.elapsed());
-        // OS Sleep logic.
-        if duration < self.target {
-            let diff = self.target - duration;
-            if diff > 1_250_000 {
-                sleep(Duration::new(0, (diff - 1_250_000) as u32));
-            }
-        }
-        // Spin sleep logic.
-        let duration = as_nanoseconds(&self.last_tick.elapsed());
+        let duration = as_nanoseconds(&(self.source.now() - self.last_tick));
         if duration < self.target {
-            let diff = self.target - duration;
-            let spin_start = Instant::now();
-            while as_nanoseconds(&spin_start.elapsed()) < diff {}
+            self.source.sleep(Duration::from_nanos(self.target - duration));
         }
-        self.delta = Clock::duration_to_delta(&self.last_tick.elapsed());
-        self.last_tick = Instant::now();
+        let now = self.source.now();
+        self.delta = duration_to_delta(&(now - self.last_tick));
+        self.last_tick = now;
+    }
+}
+
+fn tps_to_target(tps: u64) -> u64 {
+    NANOS_PER_SEC.checked_div(tps).unwrap_or(0)
+}
+
+#[inline]
+fn duration_to_delta(duration: &Duration) -> f32 {
+    (as_nanoseconds(duration) as f64 / NANOS_PER_SEC as f64) as f32
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn tick() {
+        let time = MockTime::new();
+        let mut clock = Clock::with_source(100, time.clone());
+
+        // Ticks sleep off the rest of the target.
+        time.advance(Duration::from_millis(4));
+        clock.tick();
+        assert_eq!(time.now(), Duration::from_millis(10));
+        assert_eq!(clock.get_delta(), 0.01);
+
+        // Slow ticks don't sleep.
+        time.advance(Duration::from_millis(25));
+        clock.tick();
+        assert_eq!(time.now(), Duration::from_millis(35));
+        assert_eq!(clock.get_delta(), 0.025);
     }
 }
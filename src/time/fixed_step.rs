@@ -0,0 +1,145 @@
+use crate::time::convert::*;
+use crate::time::source::*;
+use std::time::Duration;
+
+/// Runs a simulation at a fixed rate, independent of how often frames are drawn. Time passed
+/// between frames is banked, and spent in whole steps of the same length, so the simulation gives
+/// the same results at any frame rate.
+///
+/// Each frame, call tick and run the simulation once for each step it returns, then draw using
+/// get_alpha to blend between the last two simulated states.
+pub struct FixedStep<S: TimeSource = RealTime> {
+    source: S,
+    last_tick: Option<Duration>,
+    // Length of a step in nanoseconds.
+    step: u64,
+    // Time banked that hasn't been spent on a step yet, in nanoseconds.
+    accumulator: u64,
+    max_steps: u32,
+    dropped: u64,
+}
+
+impl FixedStep<RealTime> {
+    /// Creates a fixed step running at the given steps per second. Frames run at most 8 steps to
+    /// catch up by default.
+    pub fn new(tps: u64) -> FixedStep<RealTime> {
+        FixedStep::with_source(tps, RealTime::new())
+    }
+}
+
+impl<S: TimeSource> FixedStep<S> {
+    /// Creates a fixed step that measures time from the given source.
+    pub fn with_source(tps: u64, source: S) -> FixedStep<S> {
+        FixedStep {
+            source,
+            last_tick: None,
+            step: tps_to_step(tps),
+            accumulator: 0,
+            max_steps: 8,
+            dropped: 0,
+        }
+    }
+
+    /// Sets the steps per second.
+    pub fn set_tps(&mut self, tps: u64) {
+        self.step = tps_to_step(tps);
+    }
+
+    /// Sets the most steps a single tick runs. When the simulation falls further behind than this,
+    /// like after a long stall, the rest of the time is dropped instead of trying to catch up, which
+    /// would make the next frame take even longer.
+    pub fn set_max_steps(&mut self, steps: u32) {
+        self.max_steps = steps.max(1);
+    }
+
+    /// Returns the length of a step in seconds.
+    #[inline]
+    pub fn get_delta(&self) -> f32 {
+        (self.step as f64 / NANOS_PER_SEC as f64) as f32
+    }
+
+    /// Returns how far the banked time is into the next step, from [0, 1). Rendering can blend the
+    /// previous and current simulated states by this to move smoothly between steps.
+    #[inline]
+    pub fn get_alpha(&self) -> f32 {
+        (self.accumulator as f64 / self.step as f64) as f32
+    }
+
+    /// Returns the number of steps dropped so far because a tick fell too far behind.
+    #[inline]
+    pub fn get_dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Banks the time passed since the last tick, and returns the number of steps to simulate this
+    /// frame. The first tick only starts the clock, and returns 0.
+    pub fn tick(&mut self) -> u32 {
+        let now = self.source.now();
+        let elapsed = match self.last_tick {
+            Some(last_tick) => now - last_tick,
+            None => Duration::from_secs(0),
+        };
+        self.last_tick = Some(now);
+        self.advance(elapsed)
+    }
+
+    /// Banks the given time, and returns the number of steps to simulate. This is tick without
+    /// measuring the time itself, for replays, tests, or time from another source.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += as_nanoseconds(&elapsed);
+        let steps = self.accumulator / self.step;
+        self.accumulator %= self.step;
+        if steps > self.max_steps as u64 {
+            self.dropped += steps - self.max_steps as u64;
+            self.max_steps
+        } else {
+            steps as u32
+        }
+    }
+}
+
+fn tps_to_step(tps: u64) -> u64 {
+    NANOS_PER_SEC / tps.max(1)
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn accumulates() {
+        let mut step = FixedStep::new(100);
+        assert_eq!(step.get_delta(), 0.01);
+
+        // Time carries over between frames, whatever the frame rate.
+        assert_eq!(step.advance(Duration::from_millis(4)), 0);
+        assert_eq!(step.get_alpha(), 0.4);
+        assert_eq!(step.advance(Duration::from_millis(7)), 1);
+        assert!((step.get_alpha() - 0.1).abs() < 1e-6);
+        assert_eq!(step.advance(Duration::from_millis(29)), 3);
+        assert_eq!(step.get_alpha(), 0.0);
+
+        let mut total = 0;
+        for _ in 0..60 {
+            total += step.advance(Duration::from_nanos(NANOS_PER_SEC / 60));
+        }
+        assert_eq!(total, 99);
+    }
+
+    #[test]
+    fn max_steps() {
+        let mut step = FixedStep::new(100);
+        step.set_max_steps(4);
+
+        // A long stall runs the most steps allowed, and drops the rest, keeping the remainder.
+        assert_eq!(step.advance(Duration::from_millis(1005)), 4);
+        assert_eq!(step.get_dropped(), 96);
+        assert_eq!(step.get_alpha(), 0.5);
+        assert_eq!(step.advance(Duration::from_millis(5)), 1);
+    }
+}
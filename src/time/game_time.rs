@@ -0,0 +1,177 @@
+/// Token to reference a layer of game time with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TimeLayer {
+    index: usize,
+}
+
+struct Layer {
+    parent: Option<usize>,
+    scale: f32,
+    paused: bool,
+    // Parent seconds left to hold the layer still for.
+    hold: f32,
+    delta: f32,
+    elapsed: f64,
+}
+
+/// Game time, kept separately from real time so it can be slowed down, sped up and paused. Time
+/// is split into a tree of layers. Each layer runs at its own scale times its parent's, and stops
+/// while it or any of its parents are paused. For example, gameplay and UI can be separate layers
+/// under the root, so pausing gameplay for a menu leaves the menu's animations running, while
+/// slowing the root slows everything.
+///
+/// Feed the real time passed each frame into update, like Clock::get_delta, then hand each system
+/// the delta of its layer.
+pub struct GameTime {
+    layers: Vec<Layer>,
+}
+
+impl GameTime {
+    /// Creates game time with just the root layer.
+    pub fn new() -> GameTime {
+        GameTime {
+            layers: vec![Layer {
+                parent: None,
+                scale: 1.0,
+                paused: false,
+                hold: 0.0,
+                delta: 0.0,
+                elapsed: 0.0,
+            }],
+        }
+    }
+
+    /// Returns the root layer, which every other layer is under.
+    pub fn root(&self) -> TimeLayer {
+        TimeLayer {
+            index: 0,
+        }
+    }
+
+    /// Creates a new layer under the parent, running at its parent's rate.
+    pub fn layer_create(&mut self, parent: &TimeLayer) -> TimeLayer {
+        self.layers.push(Layer {
+            parent: Some(parent.index),
+            scale: 1.0,
+            paused: false,
+            hold: 0.0,
+            delta: 0.0,
+            elapsed: 0.0,
+        });
+        TimeLayer {
+            index: self.layers.len() - 1,
+        }
+    }
+
+    /// Sets how fast the layer runs compared to its parent. 0.5 runs at half speed, like slow
+    /// motion. Negative scales are treated as 0.0.
+    pub fn layer_scale(&mut self, layer: &TimeLayer, scale: f32) {
+        self.layers[layer.index].scale = scale.max(0.0);
+    }
+
+    /// Pauses or unpauses the layer. Pausing a layer also stops every layer under it.
+    pub fn layer_pause(&mut self, layer: &TimeLayer, paused: bool) {
+        self.layers[layer.index].paused = paused;
+    }
+
+    /// Stops the layer for the seconds of its parent's time, then lets it run again, like the
+    /// brief freeze when a hit lands. Holding a layer that's already held keeps the longer hold.
+    pub fn layer_hold(&mut self, layer: &TimeLayer, seconds: f32) {
+        let hold = &mut self.layers[layer.index].hold;
+        *hold = hold.max(seconds);
+    }
+
+    /// Returns if the layer is paused. This doesn't include its parents being paused.
+    pub fn is_paused(&self, layer: &TimeLayer) -> bool {
+        self.layers[layer.index].paused
+    }
+
+    /// Returns the seconds the layer moved forward in the last update.
+    pub fn get_delta(&self, layer: &TimeLayer) -> f32 {
+        self.layers[layer.index].delta
+    }
+
+    /// Returns the seconds the layer has moved forward in total.
+    pub fn get_elapsed(&self, layer: &TimeLayer) -> f64 {
+        self.layers[layer.index].elapsed
+    }
+
+    /// Moves every layer forward by the real seconds passed since the last update.
+    pub fn update(&mut self, delta: f32) {
+        // Parents are always created before their children, so they're updated first.
+        for index in 0..self.layers.len() {
+            let parent = match self.layers[index].parent {
+                Some(parent) => self.layers[parent].delta,
+                None => delta.max(0.0),
+            };
+            let layer = &mut self.layers[index];
+            let mut available = if layer.paused {
+                0.0
+            } else {
+                parent
+            };
+            if layer.hold > 0.0 {
+                let held = layer.hold.min(available);
+                layer.hold -= held;
+                available -= held;
+            }
+            layer.delta = available * layer.scale;
+            layer.elapsed += layer.delta as f64;
+        }
+    }
+}
+
+impl Default for GameTime {
+    fn default() -> GameTime {
+        GameTime::new()
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn layers() {
+        let mut time = GameTime::new();
+        let root = time.root();
+        let gameplay = time.layer_create(&root);
+        let ui = time.layer_create(&root);
+        let effects = time.layer_create(&gameplay);
+
+        // Scales multiply down the tree.
+        time.layer_scale(&root, 0.5);
+        time.layer_scale(&effects, 0.5);
+        time.update(1.0);
+        assert_eq!(time.get_delta(&gameplay), 0.5);
+        assert_eq!(time.get_delta(&effects), 0.25);
+
+        // Pausing stops the layer and everything under it.
+        time.layer_pause(&gameplay, true);
+        time.update(1.0);
+        assert_eq!(time.get_delta(&gameplay), 0.0);
+        assert_eq!(time.get_delta(&effects), 0.0);
+        assert_eq!(time.get_delta(&ui), 0.5);
+        assert_eq!(time.get_elapsed(&ui), 1.0);
+        assert_eq!(time.get_elapsed(&effects), 0.25);
+    }
+
+    #[test]
+    fn hold() {
+        let mut time = GameTime::new();
+        let root = time.root();
+        let gameplay = time.layer_create(&root);
+        time.layer_hold(&gameplay, 0.25);
+        time.update(0.125);
+        assert_eq!(time.get_delta(&gameplay), 0.0);
+        time.update(0.25);
+        assert_eq!(time.get_delta(&gameplay), 0.125);
+        time.update(0.25);
+        assert_eq!(time.get_delta(&gameplay), 0.25);
+    }
+}
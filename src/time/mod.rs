@@ -1,7 +1,13 @@
 mod clock;
 mod convert;
+mod fixed_step;
+mod game_time;
+mod source;
 mod timer;
 
 pub use crate::time::clock::*;
 pub use crate::time::convert::*;
+pub use crate::time::fixed_step::*;
+pub use crate::time::game_time::*;
+pub use crate::time::source::*;
 pub use crate::time::timer::*;
@@ -0,0 +1,84 @@
+use crate::time::convert::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+/// A source of time for clocks to measure with. Clocks use RealTime by default, and MockTime can
+/// stand in for it to control time directly, like in tests.
+pub trait TimeSource {
+    /// Returns the time passed since the source was created.
+    fn now(&self) -> Duration;
+
+    /// Waits for the duration to pass.
+    fn sleep(&self, duration: Duration);
+}
+
+/// Time from the system's monotonic clock.
+#[derive(Copy, Clone, Debug)]
+pub struct RealTime {
+    start: Instant,
+}
+
+impl RealTime {
+    pub fn new() -> RealTime {
+        RealTime {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for RealTime {
+    fn default() -> RealTime {
+        RealTime::new()
+    }
+}
+
+impl TimeSource for RealTime {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Sleeps the thread until the last 1.25ms, then spins for the rest, since OS sleeps can
+    /// overshoot by about that much.
+    fn sleep(&self, duration: Duration) {
+        let start = Instant::now();
+        let target = as_nanoseconds(&duration);
+        // OS Sleep logic.
+        if target > 1_250_000 {
+            thread::sleep(Duration::from_nanos(target - 1_250_000));
+        }
+        // Spin sleep logic.
+        while as_nanoseconds(&start.elapsed()) < target {}
+    }
+}
+
+/// Time that only moves when it's told to. Clones share the same time, so a clone can be kept to
+/// move the time of a clock it was given to. Sleeping moves the time forward instantly.
+#[derive(Clone, Debug, Default)]
+pub struct MockTime {
+    nanos: Arc<AtomicU64>,
+}
+
+impl MockTime {
+    /// Creates a mock time starting at zero.
+    pub fn new() -> MockTime {
+        MockTime::default()
+    }
+
+    /// Moves the time forward by the duration.
+    pub fn advance(&self, duration: Duration) {
+        self.nanos.fetch_add(as_nanoseconds(&duration), Ordering::Relaxed);
+    }
+}
+
+impl TimeSource for MockTime {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
@@ -1,20 +1,30 @@
 use crate::time::convert::*;
-use std::time::Instant;
+use crate::time::source::*;
+use std::time::Duration;
 
-pub struct Timer {
+pub struct Timer<S: TimeSource = RealTime> {
+    source: S,
     label: &'static str,
-    start: Instant,
-    last_display: Instant,
+    start: Duration,
+    last_display: Duration,
     duration: u64,
     invocations: u64,
 }
 
-impl Timer {
-    pub fn new(label: &'static str) -> Timer {
+impl Timer<RealTime> {
+    pub fn new(label: &'static str) -> Timer<RealTime> {
+        Timer::with_source(label, RealTime::new())
+    }
+}
+
+impl<S: TimeSource> Timer<S> {
+    /// Creates a timer that measures time from the given source.
+    pub fn with_source(label: &'static str, source: S) -> Timer<S> {
         Timer {
             label,
-            start: Instant::now(),
-            last_display: Instant::now(),
+            start: source.now(),
+            last_display: source.now(),
+            source,
             duration: 0,
             invocations: 0,
         }
@@ -22,17 +32,18 @@ impl Timer {
 
     #[inline]
     pub fn start(&mut self) {
-        self.start = Instant::now();
+        self.start = self.source.now();
     }
 
     #[inline]
     pub fn stop(&mut self) {
-        self.duration += as_nanoseconds(&self.start.elapsed());
+        let now = self.source.now();
+        self.duration += as_nanoseconds(&(now - self.start));
         self.invocations += 1;
-        if as_nanoseconds(&self.last_display.elapsed()) > NANOS_PER_SEC {
-            self.last_display = Instant::now();
+        if as_nanoseconds(&(now - self.last_display)) > NANOS_PER_SEC {
+            self.last_display = now;
             let average = (self.duration as f32) / (self.invocations as f32);
-            let max_tps = NANOS_PER_SEC / (average as u64);
+            let max_tps = NANOS_PER_SEC / (average as u64).max(1);
             trace!("{:16}: {:4} / {:7} tps | {:7.0} ns", self.label, self.invocations, max_tps, average);
             self.duration = 0;
             self.invocations = 0;
@@ -0,0 +1,134 @@
+use crate::tween::easing::*;
+use crate::tween::lerp::*;
+
+/// Something that plays out over time, like a tween or a sequence of them. Animations are driven
+/// by the seconds passed each frame, like Clock::get_delta or GameTime::get_delta, so pausing or
+/// slowing the time pauses or slows the animation.
+pub trait Animation {
+    /// Moves the animation forward by the seconds. Returns the seconds left over once the
+    /// animation finished, or 0.0 while it's still playing. Updating a finished animation returns
+    /// all of the seconds.
+    fn update(&mut self, delta: f32) -> f32;
+
+    /// Returns if the animation has played to its end.
+    fn is_finished(&self) -> bool;
+
+    /// Puts the animation back to its start, to play it again.
+    fn reset(&mut self);
+}
+
+/// Moves a value from a start to an end over a number of seconds, following an easing curve.
+pub struct Tween<T: Lerp> {
+    from: T,
+    to: T,
+    duration: f32,
+    ease: Ease,
+    elapsed: f32,
+    finished: bool,
+    on_update: Option<Box<dyn FnMut(T)>>,
+}
+
+impl<T: Lerp> Tween<T> {
+    /// Creates a tween from the start to the end over the seconds.
+    pub fn new(from: T, to: T, duration: f32, ease: Ease) -> Tween<T> {
+        Tween {
+            from,
+            to,
+            duration: duration.max(0.0),
+            ease,
+            elapsed: 0.0,
+            finished: false,
+            on_update: None,
+        }
+    }
+
+    /// Calls the callback with the value each time the tween moves. This is how the values of
+    /// tweens inside sequences are read.
+    pub fn on_update<F: FnMut(T) + 'static>(mut self, callback: F) -> Tween<T> {
+        self.on_update = Some(Box::new(callback));
+        self
+    }
+
+    /// Returns the current value. Finished tweens always return exactly the end value.
+    pub fn get_value(&self) -> T {
+        if self.elapsed >= self.duration {
+            self.to
+        } else {
+            self.from.lerp(self.to, self.ease.apply(self.get_progress()))
+        }
+    }
+
+    /// Returns how far through the tween the time is, from 0.0 to 1.0.
+    pub fn get_progress(&self) -> f32 {
+        if self.duration > 0.0 {
+            self.elapsed / self.duration
+        } else {
+            1.0
+        }
+    }
+
+    /// Changes the start and end of the tween, and puts it back to its start. This is handy for
+    /// tweening from wherever a value is now to a new target.
+    pub fn retarget(&mut self, from: T, to: T) {
+        self.from = from;
+        self.to = to;
+        self.reset();
+    }
+}
+
+impl<T: Lerp> Animation for Tween<T> {
+    fn update(&mut self, delta: f32) -> f32 {
+        if self.is_finished() {
+            return delta;
+        }
+        self.elapsed += delta.max(0.0);
+        let left = (self.elapsed - self.duration).max(0.0);
+        self.elapsed = self.elapsed.min(self.duration);
+        self.finished = self.elapsed >= self.duration;
+        let value = self.get_value();
+        if let Some(callback) = &mut self.on_update {
+            callback(value);
+        }
+        left
+    }
+
+    fn is_finished(&self) -> bool {
+        // Tweens with no duration still finish on an update, so their callback sees the end value.
+        self.finished
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.finished = false;
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+    use crate::colors::*;
+    use cgmath::*;
+
+    #[test]
+    fn tween() {
+        let mut tween = Tween::new(Vector2::new(0.0, 10.0), Vector2::new(10.0, 0.0), 2.0, Ease::Linear);
+        assert_eq!(tween.update(0.5), 0.0);
+        assert_eq!(tween.get_value(), Vector2::new(2.5, 7.5));
+        assert_eq!(tween.update(2.0), 0.5);
+        assert!(tween.is_finished());
+        assert_eq!(tween.get_value(), Vector2::new(10.0, 0.0));
+
+        // Colors are clamped when the curve overshoots.
+        let mut tween = Tween::new(BLACK, WHITE, 1.0, Ease::BackOut);
+        tween.update(0.8);
+        assert_eq!(tween.get_value(), WHITE);
+        tween.reset();
+        tween.update(0.5);
+        assert!(tween.get_value().r > 128);
+    }
+}
@@ -0,0 +1,159 @@
+use std::f32::consts::PI;
+
+/// Curves for how a tween moves from its start to its end. In curves start slow and speed up, Out
+/// curves start fast and slow down, and InOut curves are slow at both ends.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Ease {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    /// Wobbles like a spring, going past both ends.
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    /// Pulls back before moving, or overshoots and settles back.
+    BackIn,
+    BackOut,
+    BackInOut,
+    /// Bounces like a dropped ball.
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+}
+
+impl Ease {
+    /// Maps progress through a tween, from 0.0 to 1.0, to how far the value is from its start to
+    /// its end. Progress outside of 0.0 to 1.0 is clamped. Elastic and back curves return values
+    /// outside of 0.0 to 1.0 part way through.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::QuadIn => quad(t),
+            Ease::QuadOut => out(quad, t),
+            Ease::QuadInOut => in_out(quad, t),
+            Ease::CubicIn => cubic(t),
+            Ease::CubicOut => out(cubic, t),
+            Ease::CubicInOut => in_out(cubic, t),
+            Ease::ElasticIn => elastic(t),
+            Ease::ElasticOut => out(elastic, t),
+            Ease::ElasticInOut => in_out(elastic, t),
+            Ease::BackIn => back(t),
+            Ease::BackOut => out(back, t),
+            Ease::BackInOut => in_out(back, t),
+            Ease::BounceIn => bounce(t),
+            Ease::BounceOut => out(bounce, t),
+            Ease::BounceInOut => in_out(bounce, t),
+        }
+    }
+}
+
+// Each curve is written as its In form, and mirrored to get the others.
+
+fn out(curve: fn(f32) -> f32, t: f32) -> f32 {
+    1.0 - curve(1.0 - t)
+}
+
+fn in_out(curve: fn(f32) -> f32, t: f32) -> f32 {
+    if t < 0.5 {
+        curve(t * 2.0) / 2.0
+    } else {
+        1.0 - curve(2.0 - t * 2.0) / 2.0
+    }
+}
+
+fn quad(t: f32) -> f32 {
+    t * t
+}
+
+fn cubic(t: f32) -> f32 {
+    t * t * t
+}
+
+fn elastic(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else {
+        // Three wobbles that grow toward the end.
+        -(2.0f32).powf(10.0 * t - 10.0) * ((t * 10.0 - 10.75) * (2.0 * PI / 3.0)).sin()
+    }
+}
+
+fn back(t: f32) -> f32 {
+    // How far the curve pulls back, which is about 10% of the distance.
+    const OVERSHOOT: f32 = 1.70158;
+    t * t * ((OVERSHOOT + 1.0) * t - OVERSHOOT)
+}
+
+fn bounce(t: f32) -> f32 {
+    // Landing is four parabolas, each a smaller bounce than the last. Bouncing in is landing in
+    // reverse.
+    let t = 1.0 - t;
+    let landed = if t < 1.0 / 2.75 {
+        7.5625 * t * t
+    } else if t < 2.0 / 2.75 {
+        let t = t - 1.5 / 2.75;
+        7.5625 * t * t + 0.75
+    } else if t < 2.5 / 2.75 {
+        let t = t - 2.25 / 2.75;
+        7.5625 * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / 2.75;
+        7.5625 * t * t + 0.984375
+    };
+    1.0 - landed
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn curves() {
+        let eases = [
+            Ease::Linear,
+            Ease::QuadIn,
+            Ease::QuadOut,
+            Ease::QuadInOut,
+            Ease::CubicIn,
+            Ease::CubicOut,
+            Ease::CubicInOut,
+            Ease::ElasticIn,
+            Ease::ElasticOut,
+            Ease::ElasticInOut,
+            Ease::BackIn,
+            Ease::BackOut,
+            Ease::BackInOut,
+            Ease::BounceIn,
+            Ease::BounceOut,
+            Ease::BounceInOut,
+        ];
+        for &ease in &eases {
+            assert!(ease.apply(0.0).abs() < 1e-5, "{:?}", ease);
+            assert!((ease.apply(1.0) - 1.0).abs() < 1e-5, "{:?}", ease);
+        }
+        assert_eq!(Ease::QuadIn.apply(0.5), 0.25);
+        assert_eq!(Ease::QuadOut.apply(0.5), 0.75);
+        assert_eq!(Ease::CubicInOut.apply(0.25), 0.0625);
+        assert_eq!(Ease::CubicInOut.apply(0.5), 0.5);
+        assert_eq!(Ease::Linear.apply(2.0), 1.0);
+        // Back pulls back below the start, and elastic overshoots the end.
+        assert!(Ease::BackIn.apply(0.2) < 0.0);
+        assert!(Ease::BackOut.apply(0.8) > 1.0);
+        assert!(Ease::ElasticOut.apply(0.1) > 1.0);
+        // Bounce out touches the end between bounces.
+        assert!((Ease::BounceOut.apply(1.0 / 2.75) - 1.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::types::RGBA8;
+use cgmath::*;
+
+/// Values that can be blended between, so they can be tweened.
+pub trait Lerp: Copy {
+    /// Returns the value t of the way from self to the other value. t is usually from 0.0 to 1.0,
+    /// but can go past either end with curves like Ease::BackOut.
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: f32, t: f32) -> f32 {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vector2<f32> {
+    fn lerp(self, other: Vector2<f32>, t: f32) -> Vector2<f32> {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vector3<f32> {
+    fn lerp(self, other: Vector3<f32>, t: f32) -> Vector3<f32> {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for RGBA8 {
+    /// Blends each channel separately. Channels that overshoot are clamped to 0 and 255.
+    fn lerp(self, other: RGBA8, t: f32) -> RGBA8 {
+        let channel = |from: u8, to: u8| (from as f32).lerp(to as f32, t).round().clamp(0.0, 255.0) as u8;
+        RGBA8::new_raw(
+            channel(self.r, other.r),
+            channel(self.g, other.g),
+            channel(self.b, other.b),
+            channel(self.a, other.a),
+        )
+    }
+}
@@ -0,0 +1,9 @@
+mod animation;
+mod easing;
+mod lerp;
+mod sequence;
+
+pub use crate::tween::animation::*;
+pub use crate::tween::easing::*;
+pub use crate::tween::lerp::*;
+pub use crate::tween::sequence::*;
@@ -0,0 +1,295 @@
+use crate::tween::animation::*;
+
+/// Waits for a number of seconds. In a sequence, this delays whatever comes after it.
+pub struct Delay {
+    duration: f32,
+    elapsed: f32,
+    finished: bool,
+}
+
+impl Delay {
+    pub fn new(duration: f32) -> Delay {
+        Delay {
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+            finished: false,
+        }
+    }
+}
+
+impl Animation for Delay {
+    fn update(&mut self, delta: f32) -> f32 {
+        if self.finished {
+            return delta;
+        }
+        self.elapsed += delta.max(0.0);
+        let left = (self.elapsed - self.duration).max(0.0);
+        self.elapsed = self.elapsed.min(self.duration);
+        self.finished = self.elapsed >= self.duration;
+        left
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.finished = false;
+    }
+}
+
+/// Calls a function once when it's reached, taking no time. Put a delay before it in a sequence
+/// to get a timer.
+pub struct Call {
+    callback: Box<dyn FnMut()>,
+    finished: bool,
+}
+
+impl Call {
+    pub fn new<F: FnMut() + 'static>(callback: F) -> Call {
+        Call {
+            callback: Box::new(callback),
+            finished: false,
+        }
+    }
+}
+
+impl Animation for Call {
+    fn update(&mut self, delta: f32) -> f32 {
+        if !self.finished {
+            self.finished = true;
+            (self.callback)();
+        }
+        delta
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn reset(&mut self) {
+        self.finished = false;
+    }
+}
+
+/// Plays animations one after another. Time left over when one finishes carries into the next, so
+/// the sequence keeps its timing no matter how the frames fall.
+pub struct Sequence {
+    animations: Vec<Box<dyn Animation>>,
+    current: usize,
+}
+
+impl Sequence {
+    pub fn new() -> Sequence {
+        Sequence {
+            animations: Vec::new(),
+            current: 0,
+        }
+    }
+
+    /// Adds an animation to play after the ones already in the sequence.
+    pub fn then<A: Animation + 'static>(mut self, animation: A) -> Sequence {
+        self.animations.push(Box::new(animation));
+        self
+    }
+}
+
+impl Default for Sequence {
+    fn default() -> Sequence {
+        Sequence::new()
+    }
+}
+
+impl Animation for Sequence {
+    fn update(&mut self, mut delta: f32) -> f32 {
+        while let Some(animation) = self.animations.get_mut(self.current) {
+            delta = animation.update(delta);
+            if !animation.is_finished() {
+                return 0.0;
+            }
+            self.current += 1;
+        }
+        delta
+    }
+
+    fn is_finished(&self) -> bool {
+        self.current == self.animations.len()
+    }
+
+    fn reset(&mut self) {
+        for animation in &mut self.animations {
+            animation.reset();
+        }
+        self.current = 0;
+    }
+}
+
+/// Plays animations at the same time, finishing once they all have.
+pub struct Parallel {
+    animations: Vec<Box<dyn Animation>>,
+}
+
+impl Parallel {
+    pub fn new() -> Parallel {
+        Parallel {
+            animations: Vec::new(),
+        }
+    }
+
+    /// Adds an animation to play alongside the ones already in the group.
+    pub fn with<A: Animation + 'static>(mut self, animation: A) -> Parallel {
+        self.animations.push(Box::new(animation));
+        self
+    }
+}
+
+impl Default for Parallel {
+    fn default() -> Parallel {
+        Parallel::new()
+    }
+}
+
+impl Animation for Parallel {
+    fn update(&mut self, delta: f32) -> f32 {
+        // The time left over is what remains after the longest animation finished.
+        let mut left = delta;
+        for animation in &mut self.animations {
+            left = left.min(animation.update(delta));
+        }
+        if self.is_finished() {
+            left
+        } else {
+            0.0
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.animations.iter().all(|animation| animation.is_finished())
+    }
+
+    fn reset(&mut self) {
+        for animation in &mut self.animations {
+            animation.reset();
+        }
+    }
+}
+
+/// Plays an animation over and over, either a set number of times or forever.
+pub struct Repeat<A: Animation> {
+    animation: A,
+    times: Option<u32>,
+    played: u32,
+}
+
+impl<A: Animation> Repeat<A> {
+    /// Plays the animation the given number of times in a row.
+    pub fn new(animation: A, times: u32) -> Repeat<A> {
+        Repeat {
+            animation,
+            times: Some(times),
+            played: 0,
+        }
+    }
+
+    /// Plays the animation forever. The repeat never finishes, so nothing after it in a sequence
+    /// plays.
+    pub fn forever(animation: A) -> Repeat<A> {
+        Repeat {
+            animation,
+            times: None,
+            played: 0,
+        }
+    }
+}
+
+impl<A: Animation> Animation for Repeat<A> {
+    fn update(&mut self, mut delta: f32) -> f32 {
+        while !self.is_finished() {
+            delta = self.animation.update(delta);
+            if !self.animation.is_finished() {
+                return 0.0;
+            }
+            self.played += 1;
+            self.animation.reset();
+            // An animation that takes no time would otherwise repeat forever within one update.
+            if delta <= 0.0 {
+                return 0.0;
+            }
+        }
+        delta
+    }
+
+    fn is_finished(&self) -> bool {
+        match self.times {
+            Some(times) => self.played >= times,
+            None => false,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.animation.reset();
+        self.played = 0;
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+    use crate::tween::easing::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn sequence() {
+        let value = Rc::new(Cell::new(0.0));
+        let calls = Rc::new(Cell::new(0));
+        let (a, b, c) = (value.clone(), value.clone(), calls.clone());
+        let mut sequence = Sequence::new()
+            .then(Tween::new(0.0, 1.0, 1.0, Ease::Linear).on_update(move |x| a.set(x)))
+            .then(Delay::new(0.5))
+            .then(Call::new(move || c.set(c.get() + 1)))
+            .then(Tween::new(1.0, 0.0, 1.0, Ease::Linear).on_update(move |x| b.set(x)));
+
+        sequence.update(0.5);
+        assert_eq!(value.get(), 0.5);
+        // Time left over from the first tween carries into the delay, which ends exactly here.
+        sequence.update(1.0);
+        assert_eq!(value.get(), 1.0);
+        assert_eq!(calls.get(), 1);
+        sequence.update(0.25);
+        assert_eq!(value.get(), 0.75);
+        assert_eq!(sequence.update(1.0), 0.25);
+        assert!(sequence.is_finished());
+        assert_eq!(value.get(), 0.0);
+        assert_eq!(calls.get(), 1);
+
+        sequence.reset();
+        sequence.update(10.0);
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn parallel_and_repeat() {
+        let calls = Rc::new(Cell::new(0));
+        let c = calls.clone();
+        let mut parallel = Parallel::new().with(Delay::new(1.0)).with(Delay::new(2.0));
+        assert_eq!(parallel.update(1.5), 0.0);
+        assert!(!parallel.is_finished());
+        assert_eq!(parallel.update(1.0), 0.5);
+        assert!(parallel.is_finished());
+
+        let mut repeat =
+            Repeat::new(Sequence::new().then(Delay::new(1.0)).then(Call::new(move || c.set(c.get() + 1))), 3);
+        repeat.update(2.5);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(repeat.update(1.0), 0.5);
+        assert!(repeat.is_finished());
+        assert_eq!(calls.get(), 3);
+    }
+}
@@ -0,0 +1,252 @@
+use cgmath::*;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A decoded sound, ready to be played. Sounds are cheap to clone, and clones share their samples.
+#[derive(Clone, Debug)]
+pub struct Sound {
+    // Samples of each channel, interleaved.
+    samples: Arc<Vec<f32>>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Sound {
+    pub(crate) fn new(samples: Vec<f32>, channels: u16, sample_rate: u32) -> Sound {
+        Sound {
+            samples: Arc::new(samples),
+            channels,
+            sample_rate,
+        }
+    }
+
+    pub(crate) fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    pub(crate) fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub(crate) fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Number of frames in the sound, where a frame holds one sample of each channel.
+    pub(crate) fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    /// Returns how long the sound plays for at its original pitch.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frames() as f64 / self.sample_rate as f64)
+    }
+}
+
+/// A sound streamed from a file while it plays, instead of being decoded into memory up front.
+/// Streaming suits long sounds like music, and supports the same formats as sound_load.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track {
+    /// Path of the file to stream.
+    pub path: PathBuf,
+    /// Section of the track to repeat, like the loop of a song after its intro. Once playback
+    /// reaches the end of the section it jumps back to its start without a gap, until the voice is
+    /// stopped. The default is None, which plays the track through once.
+    pub loop_points: Option<LoopPoints>,
+}
+
+impl Track {
+    /// Creates a track that plays the file through once.
+    pub fn new<P: AsRef<Path>>(path: P) -> Track {
+        Track {
+            path: path.as_ref().to_path_buf(),
+            loop_points: None,
+        }
+    }
+}
+
+/// Section of a track to repeat.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LoopPoints {
+    /// Time in the track the loop starts at.
+    pub start: Duration,
+    /// Time in the track the loop ends at, jumping back to the start. None is the end of the
+    /// track.
+    pub end: Option<Duration>,
+}
+
+/// Token to reference a playing sound with. Voices that finished playing ignore changes.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VoiceToken {
+    key: u32,
+}
+
+impl VoiceToken {
+    pub(crate) fn new(key: u32) -> VoiceToken {
+        VoiceToken {
+            key,
+        }
+    }
+
+    pub(crate) fn key(&self) -> u32 {
+        self.key
+    }
+}
+
+/// A group of sounds mixed together, with its own volume.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AudioBus {
+    /// Music, which is ducked while dialogue plays.
+    Music,
+    /// Sound effects.
+    Effects,
+    /// Dialogue and other voice over.
+    Dialogue,
+}
+
+impl AudioBus {
+    pub(crate) fn index(self) -> usize {
+        match self {
+            AudioBus::Music => 0,
+            AudioBus::Effects => 1,
+            AudioBus::Dialogue => 2,
+        }
+    }
+}
+
+/// How music is turned down while dialogue plays, so the dialogue can be heard over it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ducking {
+    /// Volume of the music while dialogue plays, where 1.0 turns ducking off. The default is 0.3.
+    pub volume: f32,
+    /// How long the music takes to turn down once dialogue starts. The default is 100ms.
+    pub attack: Duration,
+    /// How long the music takes to come back up once dialogue ends. The default is 500ms.
+    pub release: Duration,
+}
+
+impl Default for Ducking {
+    fn default() -> Ducking {
+        Ducking {
+            volume: 0.3,
+            attack: Duration::from_millis(100),
+            release: Duration::from_millis(500),
+        }
+    }
+}
+
+/// How a positioned sound gets quieter as it moves away from the listener.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Rolloff {
+    /// The volume falls evenly from full at the near distance to silent at the far distance.
+    Linear,
+    /// The volume is near / distance, halving each time the distance doubles, like sound in open
+    /// space.
+    Inverse,
+    /// The volume is (near / distance) ^ factor. Larger factors fall off faster, and a factor of
+    /// 2.0 follows the inverse square law.
+    Exponential(f32),
+}
+
+/// Where a sound plays from, relative to the listener. Positions are in the same units as
+/// Sprite::pos, so a sound can follow the sprite making it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Emitter {
+    /// Position of the sound.
+    pub position: Vector2<f32>,
+    /// How the sound gets quieter with distance. The default is Inverse.
+    pub rolloff: Rolloff,
+    /// Distance the sound plays at its full volume within. Sounds within this distance are also
+    /// panned less, so sounds on top of the listener play from both speakers. The default is 64.0.
+    pub near: f32,
+    /// Distance past which the sound gets no quieter. Linear rolloff is silent from here on. The
+    /// default is 1024.0.
+    pub far: f32,
+}
+
+impl Emitter {
+    /// Creates an emitter at the position, with the default rolloff.
+    pub fn new(position: Vector2<f32>) -> Emitter {
+        Emitter {
+            position,
+            ..Emitter::default()
+        }
+    }
+
+    /// Returns the volume and pan of the sound heard from the listener's position.
+    pub(crate) fn hear(&self, listener: Vector2<f32>) -> (f32, f32) {
+        let offset = self.position - listener;
+        let near = self.near.max(0.0);
+        let far = self.far.max(near);
+        let distance = offset.magnitude();
+        let clamped = distance.max(near).min(far);
+        let volume = if clamped <= 0.0 {
+            1.0
+        } else {
+            match self.rolloff {
+                Rolloff::Linear => {
+                    if far > near {
+                        1.0 - (clamped - near) / (far - near)
+                    } else {
+                        1.0
+                    }
+                }
+                Rolloff::Inverse => near / clamped,
+                Rolloff::Exponential(factor) => (near / clamped).powf(factor.max(0.0)),
+            }
+        };
+        let pan = if distance > 0.0 {
+            offset.x / distance.max(near)
+        } else {
+            0.0
+        };
+        (volume, pan)
+    }
+}
+
+impl Default for Emitter {
+    fn default() -> Emitter {
+        Emitter {
+            position: Vector2::new(0.0, 0.0),
+            rolloff: Rolloff::Inverse,
+            near: 64.0,
+            far: 1024.0,
+        }
+    }
+}
+
+/// Settings to play a sound with.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SoundSettings {
+    /// Volume of the sound, where 1.0 is its original volume. The default is 1.0.
+    pub volume: f32,
+    /// Position of the sound between the left and right speakers, from -1.0 for only the left to
+    /// 1.0 for only the right. The default is 0.0, centered.
+    pub pan: f32,
+    /// Speed to play the sound at, which also shifts its pitch. 2.0 plays the sound twice as fast,
+    /// an octave higher. The default is 1.0.
+    pub pitch: f32,
+    /// If the sound starts over when it ends, like music. Looping sounds play until stopped. Looping
+    /// playlists start over from their first track. The default is false.
+    pub looping: bool,
+    /// Bus the sound is mixed into. The default is Effects.
+    pub bus: AudioBus,
+    /// Where the sound plays from. Positioned sounds are panned and turned down based on where
+    /// they are relative to the listener, on top of the volume and pan above. The default is None,
+    /// which plays the sound as is.
+    pub emitter: Option<Emitter>,
+}
+
+impl Default for SoundSettings {
+    fn default() -> SoundSettings {
+        SoundSettings {
+            volume: 1.0,
+            pan: 0.0,
+            pitch: 1.0,
+            looping: false,
+            bus: AudioBus::Effects,
+            emitter: None,
+        }
+    }
+}
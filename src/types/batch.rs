@@ -1,3 +1,4 @@
+use super::TextureToken;
 use crate::utility::unordered_tracker::*;
 use cgmath::*;
 
@@ -32,6 +33,9 @@ pub struct BatchSettings {
     pub rotation: f32,
     /// If the renderer should render this batch or not.
     pub visible: bool,
+    /// Standalone texture to draw this batch's sprites with. If this is None, sprites are drawn from
+    /// the texture atlas. Text is always drawn from the texture atlas. The default is None.
+    pub texture: Option<TextureToken>,
 }
 
 impl Default for BatchSettings {
@@ -41,6 +45,7 @@ impl Default for BatchSettings {
             scale: 1.0,
             rotation: 0.0,
             visible: true,
+            texture: None,
         }
     }
 }
@@ -1,13 +1,19 @@
+mod audio;
 mod batch;
 mod color;
+mod render;
 mod sprite;
+mod sprite_sheet;
 mod text;
 mod texture;
 mod window;
 
+pub use audio::*;
 pub use batch::*;
 pub use color::*;
+pub use render::*;
 pub use sprite::*;
+pub use sprite_sheet::*;
 pub use text::*;
 pub use texture::*;
 pub use window::*;
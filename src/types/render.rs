@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+/// Counts of the work done by the renderer to draw a frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    /// Visible batches with anything in them.
+    pub batches: usize,
+    /// Sprite quads submitted.
+    pub sprites: usize,
+    /// Glyph quads submitted, from both regular and SDF text.
+    pub glyphs: usize,
+    pub draw_calls: usize,
+    /// Bytes of sprite and glyph data uploaded to buffers.
+    pub buffer_bytes: usize,
+    /// Bytes of pixels uploaded to the atlas and standalone textures.
+    pub texture_bytes: usize,
+    /// Fraction of the texture atlas in use, from 0.0 to 1.0.
+    pub atlas_occupancy: f32,
+    /// Time from the end of the render thread's last frame to the end of this one.
+    pub frame_time: Duration,
+}
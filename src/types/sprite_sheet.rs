@@ -0,0 +1,343 @@
+use super::*;
+use cgmath::*;
+use hashbrown::HashMap;
+use serde_json::Value;
+
+/// Playback direction of an animation tag.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TagDirection {
+    Forward,
+    Reverse,
+    /// Plays forward, then back in reverse.
+    PingPong,
+}
+
+/// A named range of frames in a sprite sheet, used for animations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteTag {
+    pub name: String,
+    /// Index of the first frame in the tag.
+    pub from: usize,
+    /// Index of the last frame in the tag, inclusive.
+    pub to: usize,
+    pub direction: TagDirection,
+}
+
+/// A single frame in a sprite sheet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteFrame {
+    /// Name of the frame, usually the file name of the source image.
+    pub name: String,
+    /// Region of the sheet this frame is stored in. If the frame is rotated, this region is
+    /// rotated 90 degrees clockwise from the original image.
+    pub texture: Texture,
+    /// If the frame is stored rotated 90 degrees clockwise in the sheet.
+    pub rotated: bool,
+    /// Size of the original image before transparent edges were trimmed, in pixels.
+    pub source_size: Vector2<u16>,
+    /// Offset of the trimmed image from the top left of the original image, in pixels.
+    pub offset: Vector2<u16>,
+    /// Size of the trimmed image, in pixels.
+    pub size: Vector2<u16>,
+    /// Pivot point of the frame, relative to the top left of the original image. Values are
+    /// normalized, so 0.5, 0.5 is the center. The default is the center.
+    pub pivot: Vector2<f32>,
+    /// How long the frame is shown for, in milliseconds. The default is 100.
+    pub duration: u32,
+}
+
+impl SpriteFrame {
+    /// Creates a sprite drawing this frame. The bottom left corner of the original, untrimmed
+    /// image is placed at the given position, and trimming and rotation are accounted for.
+    pub fn sprite(&self, pos: Vector3<f32>, color: RGBA8) -> Sprite {
+        // Offsets are measured from the top left, but sprites are positioned from the bottom left.
+        let x = pos.x + self.offset.x as f32;
+        let y = pos.y + self.source_size.y.saturating_sub(self.offset.y.saturating_add(self.size.y)) as f32;
+        if self.rotated {
+            // Rotated frames are drawn with their stored size and turned back a quarter turn
+            // around their center, so the center has to line up with the unrotated frame.
+            let shift = (self.size.x as f32 - self.size.y as f32) / 2.0;
+            Sprite::new_raw(
+                Vector3::new(x + shift, y - shift, pos.z),
+                Vector2::new(self.size.y, self.size.x),
+                self.texture,
+                color,
+                16384,
+            )
+        } else {
+            Sprite::new_raw(Vector3::new(x, y, pos.z), self.size, self.texture, color, 0)
+        }
+    }
+}
+
+/// Frames and animation tags imported from sprite sheet metadata. This supports the JSON exported
+/// by Aseprite and TexturePacker, in both the hash and array layouts.
+#[derive(Clone, Debug)]
+pub struct SpriteSheet {
+    frames: Vec<SpriteFrame>,
+    tags: Vec<SpriteTag>,
+    frame_lookup: HashMap<String, usize>,
+    tag_lookup: HashMap<String, usize>,
+}
+
+impl SpriteSheet {
+    /// Parses sprite sheet metadata. The given texture is the image the metadata describes, and
+    /// each frame is sliced from it.
+    ///
+    /// Returns an error if the metadata is malformed, or a frame is outside the bounds of the
+    /// texture.
+    pub fn from_json(texture: &Texture, json: &str) -> Result<SpriteSheet, &'static str> {
+        let root: Value = match serde_json::from_str(json) {
+            Ok(root) => root,
+            Err(_) => Err("Unable to parse sprite sheet JSON.")?,
+        };
+
+        let mut frames = Vec::new();
+        match &root["frames"] {
+            Value::Object(map) => {
+                for (name, value) in map {
+                    frames.push(parse_frame(texture, name, value)?);
+                }
+            }
+            Value::Array(list) => {
+                for value in list {
+                    let name = value["filename"].as_str().ok_or("Frame is missing a filename.")?;
+                    frames.push(parse_frame(texture, name, value)?);
+                }
+            }
+            _ => Err("Sprite sheet is missing frames.")?,
+        }
+
+        let mut tags = Vec::new();
+        if let Some(list) = root["meta"]["frameTags"].as_array() {
+            for value in list {
+                let tag = parse_tag(value)?;
+                if tag.from > tag.to || tag.to >= frames.len() {
+                    Err("Tag frame range is outside of the sprite sheet.")?
+                }
+                tags.push(tag);
+            }
+        }
+
+        let mut frame_lookup = HashMap::new();
+        for (index, frame) in frames.iter().enumerate() {
+            frame_lookup.insert(frame.name.clone(), index);
+        }
+        let mut tag_lookup = HashMap::new();
+        for (index, tag) in tags.iter().enumerate() {
+            tag_lookup.insert(tag.name.clone(), index);
+        }
+
+        Ok(SpriteSheet {
+            frames,
+            tags,
+            frame_lookup,
+            tag_lookup,
+        })
+    }
+
+    /// All frames in the order they appear in the metadata.
+    pub fn frames(&self) -> &[SpriteFrame] {
+        &self.frames
+    }
+
+    /// All animation tags in the order they appear in the metadata.
+    pub fn tags(&self) -> &[SpriteTag] {
+        &self.tags
+    }
+
+    /// Looks up a frame by name.
+    pub fn frame(&self, name: &str) -> Option<&SpriteFrame> {
+        self.frame_lookup.get(name).map(|&index| &self.frames[index])
+    }
+
+    /// Looks up a texture by frame name. This is a shorthand for the texture of a frame.
+    pub fn texture(&self, name: &str) -> Option<Texture> {
+        self.frame(name).map(|frame| frame.texture)
+    }
+
+    /// Looks up an animation tag by name.
+    pub fn tag(&self, name: &str) -> Option<&SpriteTag> {
+        self.tag_lookup.get(name).map(|&index| &self.tags[index])
+    }
+
+    /// Returns the frames covered by the animation tag with the given name, in sheet order.
+    pub fn tag_frames(&self, name: &str) -> Option<&[SpriteFrame]> {
+        self.tag(name).map(|tag| &self.frames[tag.from..=tag.to])
+    }
+}
+
+fn parse_frame(texture: &Texture, name: &str, value: &Value) -> Result<SpriteFrame, &'static str> {
+    let (x, y, mut w, mut h) = parse_rect(&value["frame"]).ok_or("Frame is missing its region.")?;
+    let rotated = value["rotated"].as_bool().unwrap_or(false);
+    let (offset_x, offset_y, size_x, size_y) = parse_rect(&value["spriteSourceSize"]).unwrap_or((0, 0, w, h));
+    let (source_x, source_y) = match parse_size(&value["sourceSize"]) {
+        Some(size) => size,
+        None => (size_x, size_y),
+    };
+    // Rotated frames list their unrotated size, but occupy the transposed region in the sheet.
+    if rotated {
+        std::mem::swap(&mut w, &mut h);
+    }
+    if x as u32 + w as u32 > texture.width() as u32 || y as u32 + h as u32 > texture.height() as u32 {
+        Err("Frame is outside the bounds of the sprite sheet texture.")?
+    }
+    let pivot = match (value["pivot"]["x"].as_f64(), value["pivot"]["y"].as_f64()) {
+        (Some(x), Some(y)) => Vector2::new(x as f32, y as f32),
+        _ => Vector2::new(0.5, 0.5),
+    };
+    let duration = match value["duration"].as_u64() {
+        Some(duration) => duration as u32,
+        None => 100,
+    };
+
+    Ok(SpriteFrame {
+        name: String::from(name),
+        texture: texture.sub_texture(x, y, w, h)?,
+        rotated,
+        source_size: Vector2::new(source_x, source_y),
+        offset: Vector2::new(offset_x, offset_y),
+        size: Vector2::new(size_x, size_y),
+        pivot,
+        duration,
+    })
+}
+
+fn parse_tag(value: &Value) -> Result<SpriteTag, &'static str> {
+    let name = value["name"].as_str().ok_or("Tag is missing a name.")?;
+    let from = value["from"].as_u64().ok_or("Tag is missing its first frame.")?;
+    let to = value["to"].as_u64().ok_or("Tag is missing its last frame.")?;
+    let direction = match value["direction"].as_str() {
+        None | Some("forward") => TagDirection::Forward,
+        Some("reverse") => TagDirection::Reverse,
+        Some("pingpong") | Some("pingpong_reverse") => TagDirection::PingPong,
+        Some(_) => Err("Tag has an unknown direction.")?,
+    };
+    Ok(SpriteTag {
+        name: String::from(name),
+        from: from as usize,
+        to: to as usize,
+        direction,
+    })
+}
+
+fn parse_rect(value: &Value) -> Option<(u16, u16, u16, u16)> {
+    let (x, y) = (parse_u16(&value["x"])?, parse_u16(&value["y"])?);
+    let (w, h) = parse_size(value)?;
+    Some((x, y, w, h))
+}
+
+fn parse_size(value: &Value) -> Option<(u16, u16)> {
+    Some((parse_u16(&value["w"])?, parse_u16(&value["h"])?))
+}
+
+fn parse_u16(value: &Value) -> Option<u16> {
+    let value = value.as_u64()?;
+    if value > u16::MAX as u64 {
+        None
+    } else {
+        Some(value as u16)
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+    use crate::colors::WHITE;
+    use crate::texture::PIXEL_SIZE;
+
+    fn sheet() -> Texture {
+        Texture(Vector4::new(0, 256 * PIXEL_SIZE as u16, 0, 256 * PIXEL_SIZE as u16))
+    }
+
+    #[test]
+    fn texture_packer_hash() {
+        let json = r#"{
+            "frames": {
+                "b.png": {
+                    "frame": {"x": 10, "y": 20, "w": 8, "h": 4},
+                    "rotated": true,
+                    "trimmed": true,
+                    "spriteSourceSize": {"x": 1, "y": 2, "w": 8, "h": 4},
+                    "sourceSize": {"w": 10, "h": 8},
+                    "pivot": {"x": 0.25, "y": 1.0}
+                },
+                "a.png": {
+                    "frame": {"x": 0, "y": 0, "w": 16, "h": 16},
+                    "rotated": false,
+                    "trimmed": false,
+                    "spriteSourceSize": {"x": 0, "y": 0, "w": 16, "h": 16},
+                    "sourceSize": {"w": 16, "h": 16}
+                }
+            },
+            "meta": {"image": "sheet.png"}
+        }"#;
+        let sheet = SpriteSheet::from_json(&sheet(), json).unwrap();
+        assert_eq!(sheet.frames()[0].name, "b.png");
+        assert_eq!(sheet.frames()[1].name, "a.png");
+
+        let b = sheet.frame("b.png").unwrap();
+        assert!(b.rotated);
+        assert_eq!(b.texture.width(), 4);
+        assert_eq!(b.texture.height(), 8);
+        assert_eq!(b.offset, Vector2::new(1, 2));
+        assert_eq!(b.source_size, Vector2::new(10, 8));
+        assert_eq!(b.pivot, Vector2::new(0.25, 1.0));
+
+        let sprite = b.sprite(Vector3::new(0.0, 0.0, 0.0), WHITE);
+        assert_eq!(sprite.size, Vector2::new(4, 8));
+        assert_eq!(sprite.rotation, 16384);
+        assert_eq!(sprite.pos, Vector3::new(3.0, 0.0, 0.0));
+
+        let a = sheet.texture("a.png").unwrap();
+        assert_eq!(a.width(), 16);
+        assert!(sheet.frame("c.png").is_none());
+    }
+
+    #[test]
+    fn aseprite_array() {
+        let json = r#"{
+            "frames": [
+                {"filename": "walk 0", "frame": {"x": 0, "y": 0, "w": 8, "h": 8}, "duration": 50},
+                {"filename": "walk 1", "frame": {"x": 8, "y": 0, "w": 8, "h": 8}, "duration": 75},
+                {"filename": "idle 0", "frame": {"x": 16, "y": 0, "w": 8, "h": 8}}
+            ],
+            "meta": {
+                "frameTags": [
+                    {"name": "walk", "from": 0, "to": 1, "direction": "pingpong"},
+                    {"name": "idle", "from": 2, "to": 2, "direction": "forward"}
+                ]
+            }
+        }"#;
+        let sheet = SpriteSheet::from_json(&sheet(), json).unwrap();
+        assert_eq!(sheet.frames().len(), 3);
+        assert_eq!(sheet.frame("walk 1").unwrap().duration, 75);
+        assert_eq!(sheet.frame("idle 0").unwrap().duration, 100);
+
+        let walk = sheet.tag("walk").unwrap();
+        assert_eq!(walk.direction, TagDirection::PingPong);
+        let frames = sheet.tag_frames("walk").unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].duration, 50);
+        assert_eq!(sheet.tag_frames("idle").unwrap()[0].name, "idle 0");
+    }
+
+    #[test]
+    fn malformed() {
+        let texture = sheet();
+        assert!(SpriteSheet::from_json(&texture, "{").is_err());
+        assert!(SpriteSheet::from_json(&texture, r#"{"meta": {}}"#).is_err());
+        let outside = r#"{"frames": [{"filename": "a", "frame": {"x": 250, "y": 0, "w": 8, "h": 8}}]}"#;
+        assert!(SpriteSheet::from_json(&texture, outside).is_err());
+        let tag = r#"{
+            "frames": [{"filename": "a", "frame": {"x": 0, "y": 0, "w": 8, "h": 8}}],
+            "meta": {"frameTags": [{"name": "t", "from": 0, "to": 3}]}
+        }"#;
+        assert!(SpriteSheet::from_json(&texture, tag).is_err());
+    }
+}
@@ -29,23 +29,125 @@ impl Default for FontToken {
     }
 }
 
+/// Horizontal alignment of text within its max width.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HorizontalAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// Vertical alignment of text within its max height.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VerticalAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// How text is broken into lines when it exceeds its max width.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WrapStyle {
+    /// Lines are broken between words.
+    Word,
+    /// Lines are broken between any two characters.
+    Letter,
+}
+
+/// An outline drawn around each glyph.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextOutline {
+    /// Thickness of the outline. For distance field fonts, this is limited to a few pixels at the
+    /// font's rasterized size. Units are measured in pixels.
+    pub width: u32,
+    pub color: RGBA8,
+}
+
+/// A copy of each glyph drawn behind the text at an offset.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextShadow {
+    /// Offset of the shadow from the text. Units are measured in pixels.
+    pub offset: Vector2<f32>,
+    pub color: RGBA8,
+}
+
+/// A soft glow drawn around each glyph.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextGlow {
+    /// Distance the glow extends from the glyph. Units are measured in pixels.
+    pub radius: u32,
+    pub color: RGBA8,
+}
+
+/// A run of text with its own style, drawn after the main string of a Text. Unset values inherit
+/// from the Text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextSpan {
+    /// Text that's being drawn.
+    pub string: String,
+    /// Amount to scale the span by. Inherits the scale of the text if None.
+    pub scale: Option<u32>,
+    /// Color to use for the span. Inherits the color of the text if None.
+    pub color: Option<RGBA8>,
+    /// Font to use for the span. Inherits the font of the text if None.
+    pub font: Option<FontToken>,
+}
+
+impl TextSpan {
+    /// Creates a span that inherits all of its style from the text.
+    pub fn new(string: &str) -> TextSpan {
+        TextSpan {
+            string: String::from(string),
+            scale: None,
+            color: None,
+            font: None,
+        }
+    }
+}
+
 /// Configuration settings for text.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Text {
     /// Text that's being drawn.
     pub string: String,
-    /// Position of the text. The X and Y coordinates represent the bottom left corner of the text.
-    /// The Z coordinate represents sprite depth. Units are measured in pixels.
+    /// Position of the text. The X and Y coordinates represent the bottom left corner of the box
+    /// the text is aligned within, sized by the max width and max height. Without a max width,
+    /// lines are aligned around the X position, and without a max height, the bottom of the text
+    /// sits on the Y position. The Z coordinate represents sprite depth. Units are measured in
+    /// pixels.
     pub pos: Vector3<f32>,
     /// Max width of the text before it's pushed to a new line. If this is set to None, it will
     /// not wrap text to a new line. Units are measured in pixels. The default is None.
     pub max_width: Option<f32>,
+    /// Max height of the box the text is aligned within, extending up from the Y position. Text
+    /// isn't clipped to this height. If this is set to None, vertical alignment has no effect and
+    /// the bottom of the text sits on the Y position. Units are measured in pixels. The default is
+    /// None.
+    pub max_height: Option<f32>,
+    /// Horizontal alignment of each line within the max width. If the max width is None, text is
+    /// aligned around the X position instead. The default is Center.
+    pub horizontal_align: HorizontalAlign,
+    /// Vertical alignment of the text within the max height. The default is Middle.
+    pub vertical_align: VerticalAlign,
+    /// Multiplier applied to the font's line height, used as the distance between lines. The
+    /// default is 1.0.
+    pub line_spacing: f32,
+    /// How lines are broken when they exceed the max width. The default is Word.
+    pub wrap_style: WrapStyle,
     /// Amount to scale the text by as defined by the font. The default is 24.
     pub scale: u32,
     /// Color to use for the text. The default is black.
     pub color: RGBA8,
     /// Font to use for the text. This is the engine font by default.
     pub font: FontToken,
+    /// Styled spans laid out after the string, continuing on the same line. The default is empty.
+    pub spans: Vec<TextSpan>,
+    /// Outline drawn around the text. The default is None.
+    pub outline: Option<TextOutline>,
+    /// Shadow drawn behind the text. The default is None.
+    pub shadow: Option<TextShadow>,
+    /// Glow drawn around the text. The default is None.
+    pub glow: Option<TextGlow>,
 }
 
 impl Default for Text {
@@ -54,9 +156,18 @@ impl Default for Text {
             string: String::new(),
             pos: Vector3::new(0f32, 0f32, 0f32),
             max_width: None,
+            max_height: None,
+            horizontal_align: HorizontalAlign::Center,
+            vertical_align: VerticalAlign::Middle,
+            line_spacing: 1.0,
+            wrap_style: WrapStyle::Word,
             scale: 24,
             color: BLACK,
             font: FontToken::default(),
+            spans: Vec::new(),
+            outline: None,
+            shadow: None,
+            glow: None,
         }
     }
 }
@@ -77,6 +188,7 @@ impl Text {
             scale,
             color,
             font,
+            ..Text::default()
         }
     }
 
@@ -84,4 +196,96 @@ impl Text {
         self.string.clear();
         self.string.push_str(&string);
     }
+
+    /// Replaces the string and spans with the given markup. Markup is plain text with the tags
+    /// [color=...], [size=...] and [font=...], each closed by [/color], [/size] and [/font]. Colors
+    /// are a color name like red, or hex in the form #RRGGBB or #RRGGBBAA. Sizes are a scale. Fonts
+    /// are an index into the given font list. Tags can be nested, and [[ is an escaped [.
+    ///
+    /// For example, "Give this to [color=yellow]Bob[/color]".
+    ///
+    /// Returns an error if the markup is malformed, in which case the text is unchanged.
+    pub fn set_markup(&mut self, markup: &str, fonts: &[FontToken]) -> Result<(), &'static str> {
+        self.spans = crate::text::markup::parse(markup, fonts)?;
+        self.string.clear();
+        Ok(())
+    }
+}
+
+/// Position and size of a single laid out glyph.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GlyphMetrics {
+    /// The first character of the cluster this glyph draws. Shaping can draw several characters
+    /// with one glyph, like the ligature fi, or one character with several glyphs, like Indic
+    /// vowel signs.
+    pub character: char,
+    /// Index of that character in the string followed by the spans, counted in chars. Every glyph
+    /// of a cluster shares it.
+    pub index: usize,
+    /// Index of the line the glyph is on, starting from the top line.
+    pub line: usize,
+    /// Bottom left corner of the glyph's bitmap. Units are measured in pixels.
+    pub pos: Vector2<f32>,
+    /// Size of the glyph's bitmap. Whitespace has no size. Units are measured in pixels.
+    pub size: Vector2<f32>,
+}
+
+/// A position a text cursor can be placed at, between two characters.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Caret {
+    /// Index of the line the caret is on, starting from the top line.
+    pub line: usize,
+    /// Position of the caret on the baseline of its line. Units are measured in pixels.
+    pub pos: Vector2<f32>,
+}
+
+/// Measurements of laid out text, computed with the same layout used to draw it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextMetrics {
+    /// Bottom left corner of the bounding box of the text. Units are measured in pixels.
+    pub pos: Vector2<f32>,
+    /// Size of the bounding box of the text. The box spans the full ascent and descent of each
+    /// line, and includes whitespace. Units are measured in pixels.
+    pub size: Vector2<f32>,
+    /// Number of lines the text was laid out into.
+    pub lines: usize,
+    /// Distance from the baseline to the top of a line. Units are measured in pixels.
+    pub ascent: f32,
+    /// Distance from the baseline to the bottom of a line. This is usually negative. Units are
+    /// measured in pixels.
+    pub descent: f32,
+    /// The laid out glyphs, in visual order. This is string order unless the text contains
+    /// right-to-left characters. Characters that aren't drawn, like line breaks, are skipped.
+    pub glyphs: Vec<GlyphMetrics>,
+    /// Caret positions, one before each character in the string followed by the spans, and one
+    /// after the last, so the caret at index i is placed before the i'th char. In right-to-left
+    /// text, a character's caret is on its right. Characters drawn with a single glyph split the
+    /// glyph's width between their carets.
+    pub carets: Vec<Caret>,
+}
+
+impl TextMetrics {
+    /// Returns the index of the caret closest to the given point, for placing a cursor where text
+    /// was clicked. The line is picked first, then the closest caret on that line.
+    pub fn caret_at(&self, point: Vector2<f32>) -> usize {
+        let mut best = 0;
+        let mut best_distance = (f32::INFINITY, f32::INFINITY);
+        for (index, caret) in self.carets.iter().enumerate() {
+            let bottom = caret.pos.y + self.descent;
+            let top = caret.pos.y + self.ascent;
+            let line_distance = if point.y < bottom {
+                bottom - point.y
+            } else if point.y > top {
+                point.y - top
+            } else {
+                0.0
+            };
+            let distance = (line_distance, (point.x - caret.pos.x).abs());
+            if distance < best_distance {
+                best = index;
+                best_distance = distance;
+            }
+        }
+        best
+    }
 }
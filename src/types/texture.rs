@@ -1,10 +1,149 @@
 use crate::texture::PIXEL_SIZE;
 use cgmath::*;
 
-/// Enumeration for all the loadable texture formats. Currently only PNG is supported.
+/// Enumeration for all the loadable texture formats.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum TextureFormat {
     PNG,
+    BMP,
+    /// Truevision TGA, including the run length encoded variants.
+    TGA,
+    /// The Quite OK Image format.
+    QOI,
+    /// Baseline and progressive JPEG.
+    JPEG,
+}
+
+impl TextureFormat {
+    /// Sniffs the magic bytes at the start of the given buffer to determine its format. TGA has no
+    /// magic bytes, so it's detected last from its footer or a plausible header. Returns None if
+    /// the format couldn't be determined.
+    pub fn detect(bytes: &[u8]) -> Option<TextureFormat> {
+        if bytes.starts_with(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n']) {
+            Some(TextureFormat::PNG)
+        } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+            Some(TextureFormat::JPEG)
+        } else if bytes.starts_with(b"qoif") {
+            Some(TextureFormat::QOI)
+        } else if bytes.starts_with(b"BM") {
+            Some(TextureFormat::BMP)
+        } else if bytes.ends_with(b"TRUEVISION-XFILE.\0")
+            || (bytes.len() >= 18
+                && bytes[1] <= 1
+                && [1, 2, 3, 9, 10, 11].contains(&bytes[2])
+                && [8, 15, 16, 24, 32].contains(&bytes[16]))
+        {
+            Some(TextureFormat::TGA)
+        } else {
+            None
+        }
+    }
+}
+
+/// Filtering applied when a standalone texture is drawn larger or smaller than its size.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextureFilter {
+    /// Uses the closest pixel. Best for pixel art.
+    Nearest,
+    /// Blends between the closest pixels.
+    Linear,
+}
+
+/// How a standalone texture is sampled outside of its bounds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextureWrap {
+    /// Coordinates are clamped to the edge pixels of the texture.
+    ClampToEdge,
+    /// The texture is repeated.
+    Repeat,
+    /// The texture is repeated, mirroring every other repetition.
+    MirroredRepeat,
+}
+
+/// Sampling configuration for a standalone texture.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextureSettings {
+    /// Filter used when the texture is drawn smaller than its size. The default is Nearest.
+    pub min_filter: TextureFilter,
+    /// Filter used when the texture is drawn larger than its size. The default is Nearest.
+    pub mag_filter: TextureFilter,
+    /// How the texture is sampled outside of its bounds. The default is ClampToEdge.
+    pub wrap: TextureWrap,
+    /// If mipmaps should be generated for the texture. This reduces shimmering when the texture is
+    /// drawn smaller than its size. The default is false.
+    pub mipmaps: bool,
+}
+
+impl Default for TextureSettings {
+    fn default() -> TextureSettings {
+        TextureSettings {
+            min_filter: TextureFilter::Nearest,
+            mag_filter: TextureFilter::Nearest,
+            wrap: TextureWrap::ClampToEdge,
+            mipmaps: false,
+        }
+    }
+}
+
+/// Token to reference a standalone texture with. Standalone textures are stored outside of the
+/// texture atlas, so they can have their own sampling settings. A standalone texture is drawn by
+/// assigning it to a batch through BatchSettings, where it replaces the atlas for that batch's
+/// sprites.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextureToken {
+    key: usize,
+    width: u16,
+    height: u16,
+}
+
+impl TextureToken {
+    pub(crate) fn new(key: usize, width: u16, height: u16) -> TextureToken {
+        TextureToken {
+            key,
+            width,
+            height,
+        }
+    }
+
+    pub(crate) fn key(&self) -> usize {
+        self.key
+    }
+
+    /// The scale applied to sprite UVs so they're measured in pixels of this texture, in the same
+    /// units as the texture atlas. UVs are normalized by u16::MAX on the GPU.
+    pub(crate) fn uv_scale(&self) -> Vector2<f32> {
+        Vector2::new(
+            u16::MAX as f32 / (PIXEL_SIZE * self.width as u32) as f32,
+            u16::MAX as f32 / (PIXEL_SIZE * self.height as u32) as f32,
+        )
+    }
+
+    /// Returns a texture covering the whole standalone texture, for use on sprites in a batch
+    /// drawing this standalone texture.
+    pub fn texture(&self) -> Texture {
+        Texture(Vector4::new(
+            0,
+            (self.width as u32 * PIXEL_SIZE) as u16,
+            0,
+            (self.height as u32 * PIXEL_SIZE) as u16,
+        ))
+    }
+
+    /// Returns a texture that repeats the standalone texture the given number of times along each
+    /// axis. This requires the texture to use a Repeat or MirroredRepeat wrap.
+    ///
+    /// Returns an error if a repeat count is 0, or the repeated size exceeds 65535 / 16 pixels.
+    pub fn tile(&self, x: u16, y: u16) -> Result<Texture, &'static str> {
+        if x == 0 || y == 0 {
+            Err("Repeat count must be greater than 0")?
+        }
+        let right = self.width as u32 * x as u32 * PIXEL_SIZE;
+        let bottom = self.height as u32 * y as u32 * PIXEL_SIZE;
+        if right > u16::MAX as u32 || bottom > u16::MAX as u32 {
+            Err("Requested tiling exceeds the maximum texture coordinate.")?
+        }
+        Ok(Texture(Vector4::new(0, right as u16, 0, bottom as u16)))
+    }
 }
 
 /// Token to reference a texture with. Has basic configuration settings.
@@ -20,6 +159,16 @@ impl Default for Texture {
 }
 
 impl Texture {
+    /// Returns the width of the texture in pixels.
+    pub fn width(&self) -> u16 {
+        (std::cmp::max(self.0.x, self.0.y) - std::cmp::min(self.0.x, self.0.y)) / (PIXEL_SIZE as u16)
+    }
+
+    /// Returns the height of the texture in pixels.
+    pub fn height(&self) -> u16 {
+        (std::cmp::max(self.0.z, self.0.w) - std::cmp::min(self.0.z, self.0.w)) / (PIXEL_SIZE as u16)
+    }
+
     /// Mirrors the texture along the Y axis. Creates a new texture.
     pub fn mirror_y(&self) -> Texture {
         Texture(Vector4::new(self.0.y, self.0.x, self.0.z, self.0.w))
@@ -67,3 +216,53 @@ impl Texture {
         Ok(Texture(subset))
     }
 }
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused_imports)]
+    use super::*;
+
+    // Where a normalized UV lands in the standalone texture, in pixels.
+    fn pixel(uv: u16, scale: f32) -> f32 {
+        uv as f32 / u16::MAX as f32 * scale
+    }
+
+    #[test]
+    fn standalone_uv_scale() {
+        let token = TextureToken::new(0, 100, 40);
+        let scale = token.uv_scale();
+        let texture = token.texture();
+        assert_eq!((texture.0.x, texture.0.z), (0, 0));
+        assert!((pixel(texture.0.y, scale.x) - 1.0).abs() < 1e-6);
+        assert!((pixel(texture.0.w, scale.y) - 1.0).abs() < 1e-6);
+        assert_eq!((texture.width(), texture.height()), (100, 40));
+
+        // Sub textures are measured in pixels of the standalone texture.
+        let sub = texture.sub_texture(25, 10, 50, 20).unwrap();
+        assert!((pixel(sub.0.x, scale.x) - 0.25).abs() < 1e-6);
+        assert!((pixel(sub.0.y, scale.x) - 0.75).abs() < 1e-6);
+        assert!((pixel(sub.0.z, scale.y) - 0.25).abs() < 1e-6);
+        assert!((pixel(sub.0.w, scale.y) - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn standalone_tile() {
+        let token = TextureToken::new(0, 100, 40);
+        let scale = token.uv_scale();
+        let tiled = token.tile(3, 2).unwrap();
+        assert_eq!(tiled.0.x, 0);
+        assert!((pixel(tiled.0.y, scale.x) - 3.0).abs() < 1e-6);
+        assert!((pixel(tiled.0.w, scale.y) - 2.0).abs() < 1e-6);
+        assert_eq!(token.tile(1, 1).unwrap(), token.texture());
+
+        assert!(token.tile(0, 1).is_err());
+        assert!(token.tile(1, 0).is_err());
+        // 100 pixels repeated 41 times is 65600 units, past the largest u16.
+        assert!(token.tile(41, 1).is_err());
+        assert!(token.tile(40, 1).is_ok());
+    }
+}
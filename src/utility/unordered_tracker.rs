@@ -7,6 +7,14 @@ pub struct Key<T> {
     phantom: PhantomData<T>,
 }
 
+impl<T> Key<T> {
+    /// Returns the index of the slot the key occupies. This is unique among live keys, and is
+    /// reused once the key is removed.
+    pub fn slot(&self) -> usize {
+        self.index as usize
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 struct Slot<T> {
     a_index: u32,